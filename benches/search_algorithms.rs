@@ -6,6 +6,7 @@ extern crate ants_ai_challenge_api;
 
 use ants_ai_challenge_api::*;
 use bencher::Bencher;
+use jockbot_ants_bot::deadline::Deadline;
 use jockbot_ants_bot::strategy::search::*;
 use jockbot_ants_bot::world_step::*;
 
@@ -39,7 +40,14 @@ fn search(search: Box<dyn Search>) {
     ants.insert(pos(0, 109));
 
     let actual = search
-        .search(&world_step, &ants, pos(0, 0), 10, 500)
+        .search(
+            &world_step,
+            &ants,
+            pos(0, 0),
+            10,
+            500,
+            &Deadline::never(),
+        )
         .iter()
         .flat_map(|sr| sr.first_order(&size))
         .collect::<Vec<Order>>();
@@ -68,5 +76,68 @@ fn search_bfs(b: &mut Bencher) {
 fn search_a_star(b: &mut Bencher) {
     b.iter(|| search(Box::new(RepeatedAStar {})));
 }
-benchmark_group!(benches, search_bfs, search_a_star);
+
+fn search_distance_field(b: &mut Bencher) {
+    b.iter(|| search(Box::new(DistanceFieldSearch {})));
+}
+
+fn search_jump_point(b: &mut Bencher) {
+    b.iter(|| search(Box::new(JumpPointSearch {})));
+}
+
+fn search_bidirectional(b: &mut Bencher) {
+    b.iter(|| search(Box::new(BidirectionalSearch {})));
+}
+
+/// A 200x200 world split by walls of water every 20 columns, each
+/// wall open at alternating ends, so far targets need winding paths.
+fn walled_world() -> AvoidWaterFilter<GridWorldStep> {
+    let mut world = WorldState::default();
+    for col in (10..200).step_by(20) {
+        let gap = if col % 40 == 10 { 0..10 } else { 100..110 };
+        for row in (0..200).filter(|r| !gap.contains(r)) {
+            world = world.water(pos(row, col));
+        }
+    }
+    AvoidWaterFilter::new(GridWorldStep::new(world, pos(200, 200)))
+}
+
+/// Search from a group of ants to a target far across the walls.
+fn search_far(search: &dyn Search, world_step: &dyn WorldStep) {
+    let ants: HashSet<Position> = (50..55)
+        .flat_map(|row| (2..7).map(move |col| pos(row, col)))
+        .collect();
+
+    let found = search.search(
+        world_step,
+        &ants,
+        pos(150, 95),
+        3,
+        1000,
+        &Deadline::never(),
+    );
+    assert_eq!(found.len(), 3);
+}
+
+fn far_target_distance_field(b: &mut Bencher) {
+    let world_step = walled_world();
+    b.iter(|| search_far(&DistanceFieldSearch {}, &world_step));
+}
+
+fn far_target_hierarchical(b: &mut Bencher) {
+    let world_step = walled_world();
+    // The cluster graph is built in the first search and kept.
+    let search = HierarchicalSearch::default();
+    b.iter(|| search_far(&search, &world_step));
+}
+benchmark_group!(
+    benches,
+    search_bfs,
+    search_a_star,
+    search_distance_field,
+    search_jump_point,
+    search_bidirectional,
+    far_target_distance_field,
+    far_target_hierarchical
+);
 benchmark_main!(benches);
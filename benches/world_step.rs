@@ -0,0 +1,124 @@
+#[macro_use]
+extern crate bencher;
+extern crate ants_ai_challenge_api;
+extern crate jockbot_ants_bot;
+
+use ants_ai_challenge_api::*;
+use bencher::Bencher;
+use jockbot_ants_bot::deadline::Deadline;
+use jockbot_ants_bot::strategy::search::*;
+use jockbot_ants_bot::world_step::*;
+
+use std::collections::HashSet;
+
+const SIZE: u16 = 200;
+
+/// A 200x200 world with ants of two players on every other tile of
+/// every other row, and some water and food in between.
+fn crowded_world() -> WorldState {
+    let mut world = WorldState::default()
+        .hill(pos(0, 0), 0)
+        .hill(pos(100, 100), 1);
+
+    for row in (0..SIZE).step_by(2) {
+        for col in (0..SIZE).step_by(2) {
+            let player = if (row + col) % 4 == 0 { 0 } else { 1 };
+            world = world.live_ant(pos(row, col), player);
+        }
+        world = world
+            .water(pos(row + 1, row % 7))
+            .food(pos(row + 1, SIZE - 1 - row % 5));
+    }
+    world
+}
+
+fn size() -> Position {
+    pos(SIZE, SIZE)
+}
+
+/// Look up every tile of the world once.
+fn look_up_all_tiles(world_step: &dyn WorldStep) -> usize {
+    let mut water = 0;
+    for row in 0..SIZE {
+        for col in 0..SIZE {
+            if world_step.tile(&pos(row, col)) == Tile::Water {
+                water += 1;
+            }
+        }
+    }
+    water
+}
+
+/// Search from all my ants to a target, avoiding water.
+fn search_through_crowd(world_step: &dyn WorldStep) {
+    let ants: HashSet<Position> =
+        world_step.all_my_ants().into_iter().collect();
+
+    let found = BFS {}.search(
+        world_step,
+        &ants,
+        pos(51, 51),
+        4,
+        20,
+        &Deadline::never(),
+    );
+    assert_eq!(found.len(), 4);
+}
+
+fn tile_lookup_basic(b: &mut Bencher) {
+    let world_step = BasicWorldStep::new(crowded_world(), size());
+    // Only one row, scanning all ants is too slow for the whole map.
+    b.iter(|| {
+        (0..SIZE)
+            .filter(|&col| {
+                world_step.tile(&pos(7, col)) == Tile::Water
+            })
+            .count()
+    });
+}
+
+fn tile_lookup_grid(b: &mut Bencher) {
+    let world_step = GridWorldStep::new(crowded_world(), size());
+    b.iter(|| {
+        (0..SIZE)
+            .filter(|&col| {
+                world_step.tile(&pos(7, col)) == Tile::Water
+            })
+            .count()
+    });
+}
+
+fn build_world_and_grid_and_look_up_all_tiles(b: &mut Bencher) {
+    b.iter(|| {
+        look_up_all_tiles(&GridWorldStep::new(
+            crowded_world(),
+            size(),
+        ))
+    });
+}
+
+fn search_basic(b: &mut Bencher) {
+    let world_step = AvoidWaterFilter::new(BasicWorldStep::new(
+        crowded_world(),
+        size(),
+    ));
+    b.iter(|| search_through_crowd(&world_step));
+}
+
+fn search_grid(b: &mut Bencher) {
+    let world_step = AvoidWaterFilter::new(GridWorldStep::new(
+        crowded_world(),
+        size(),
+    ));
+    b.iter(|| search_through_crowd(&world_step));
+}
+
+benchmark_group!(
+    benches,
+    tile_lookup_basic,
+    tile_lookup_grid,
+    build_world_and_grid_and_look_up_all_tiles,
+    search_basic,
+    search_grid
+);
+benchmark_main!(benches);
@@ -0,0 +1,243 @@
+use crate::strategy::search::SearchResult;
+use ants_ai_challenge_api::*;
+use std::collections::{HashMap, HashSet};
+
+/// Identity of one of my ants, stable across turns.
+pub type AntId = u32;
+
+/// Commitment of an ant to reach a target, given by a strategy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mission {
+    /// Name of the strategy giving the mission.
+    pub strategy: &'static str,
+    pub target: Position,
+    /// Planned path, from the current position of the ant to the
+    /// target.
+    pub path: SearchResult,
+}
+
+impl Mission {
+    pub fn new(
+        strategy: &'static str,
+        path: SearchResult,
+    ) -> Mission {
+        Mission {
+            strategy,
+            target: path.last_step(),
+            path,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TrackedAnt {
+    id: AntId,
+    mission: Option<Mission>,
+}
+
+/// Tracks my ants between turns, by matching the positions of last
+/// turn and the orders given to the positions of this turn. Each ant
+/// gets a stable id and can be given a mission, kept while the ant
+/// follows the planned path.
+#[derive(Default)]
+pub struct AntTracker {
+    next_id: AntId,
+    ants: HashMap<Position, TrackedAnt>,
+}
+
+impl AntTracker {
+    /// Update with my ants at the start of a turn. Ants not where
+    /// expected get new ids, missions of ants at their target end.
+    pub fn update(&mut self, my_ants: &[Position]) {
+        let mut ants = HashMap::new();
+
+        for ant in my_ants {
+            let tracked = match self.ants.remove(ant) {
+                Some(mut tracked) => {
+                    let left = tracked
+                        .mission
+                        .as_ref()
+                        .map(|m| m.path.order_length());
+                    if left == Some(0) {
+                        tracked.mission = None;
+                    }
+                    tracked
+                }
+                None => {
+                    self.next_id += 1;
+                    TrackedAnt {
+                        id: self.next_id,
+                        mission: None,
+                    }
+                }
+            };
+            ants.insert(ant.clone(), tracked);
+        }
+        self.ants = ants;
+    }
+
+    /// Record orders given this turn, moving each tracked ant to the
+    /// position expected next turn. Missions of ants not following
+    /// their planned path end.
+    pub fn record_orders(
+        &mut self,
+        orders: &[Order],
+        size: &Position,
+    ) {
+        let moves: HashMap<&Position, &Order> =
+            orders.iter().map(|o| (&o.pos, o)).collect();
+        let mut ants = HashMap::new();
+        let mut collided = HashSet::new();
+
+        for (ant, mut tracked) in self.ants.drain() {
+            let next = match moves.get(&ant) {
+                Some(order) => order.target_pos(size),
+                None => ant.clone(),
+            };
+
+            tracked.mission = tracked.mission.take().and_then(|m| {
+                if next == ant {
+                    Some(m)
+                } else if m.path.order_length() > 0
+                    && m.path.without_first_step().first_step()
+                        == next
+                {
+                    let path = m.path.without_first_step();
+                    Some(Mission { path, ..m })
+                } else {
+                    None
+                }
+            });
+
+            // Ants expected at the same position collide and die,
+            // also any further ant moving there.
+            if collided.contains(&next)
+                || ants.insert(next.clone(), tracked).is_some()
+            {
+                ants.remove(&next);
+                collided.insert(next);
+            }
+        }
+        self.ants = ants;
+    }
+
+    pub fn id(&self, ant: &Position) -> Option<AntId> {
+        self.ants.get(ant).map(|t| t.id)
+    }
+
+    pub fn mission(&self, ant: &Position) -> Option<&Mission> {
+        self.ants.get(ant).and_then(|t| t.mission.as_ref())
+    }
+
+    /// Give mission to tracked ant, starting its path at the ant.
+    pub fn set_mission(&mut self, mission: Mission) {
+        if let Some(tracked) =
+            self.ants.get_mut(&mission.path.first_step())
+        {
+            tracked.mission = Some(mission);
+        }
+    }
+
+    pub fn clear_mission(&mut self, ant: &Position) {
+        if let Some(tracked) = self.ants.get_mut(ant) {
+            tracked.mission = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(steps: &[Position]) -> SearchResult {
+        steps[1..]
+            .iter()
+            .fold(SearchResult::start(steps[0].clone()), |r, s| {
+                r.add_step(s.clone())
+            })
+    }
+
+    #[test]
+    fn keep_ids_of_ants_moving_as_ordered() {
+        let size = &pos(10, 10);
+        let mut tracker = AntTracker::default();
+
+        tracker.update(&[pos(1, 1), pos(5, 5)]);
+        let first = tracker.id(&pos(1, 1));
+        let second = tracker.id(&pos(5, 5));
+        tracker.record_orders(&[pos(1, 1).east()], size);
+        tracker.update(&[pos(1, 2), pos(5, 5), pos(9, 9)]);
+
+        assert_eq![tracker.id(&pos(1, 2)), first];
+        assert_eq![tracker.id(&pos(5, 5)), second];
+        assert_eq![tracker.id(&pos(9, 9)), Some(3)];
+        assert_eq![tracker.id(&pos(1, 1)), None];
+    }
+
+    #[test]
+    fn new_ids_for_ants_not_where_expected() {
+        let size = &pos(10, 10);
+        let mut tracker = AntTracker::default();
+
+        tracker.update(&[pos(1, 1)]);
+        tracker.record_orders(&[pos(1, 1).east()], size);
+        tracker.update(&[pos(1, 1)]);
+
+        assert_eq![tracker.id(&pos(1, 1)), Some(2)];
+    }
+
+    #[test]
+    fn forget_all_ants_colliding_on_same_square() {
+        let size = &pos(10, 10);
+        let mut tracker = AntTracker::default();
+
+        tracker.update(&[pos(1, 1), pos(1, 3), pos(0, 2)]);
+        tracker.record_orders(
+            &[pos(1, 1).east(), pos(1, 3).west(), pos(0, 2).south()],
+            size,
+        );
+        tracker.update(&[pos(1, 2)]);
+
+        assert_eq![tracker.id(&pos(1, 2)), Some(4)];
+    }
+
+    #[test]
+    fn follow_mission_path_until_target() {
+        let size = &pos(10, 10);
+        let mut tracker = AntTracker::default();
+        tracker.update(&[pos(1, 1)]);
+        tracker.set_mission(Mission::new(
+            "test",
+            path(&[pos(1, 1), pos(1, 2), pos(1, 3)]),
+        ));
+
+        tracker.record_orders(&[pos(1, 1).east()], size);
+        tracker.update(&[pos(1, 2)]);
+
+        let mission = tracker.mission(&pos(1, 2)).expect("mission");
+        assert_eq![mission.target, pos(1, 3)];
+        assert_eq![mission.path, path(&[pos(1, 2), pos(1, 3)])];
+
+        tracker.record_orders(&[pos(1, 2).east()], size);
+        tracker.update(&[pos(1, 3)]);
+
+        assert_eq![tracker.mission(&pos(1, 3)), None];
+    }
+
+    #[test]
+    fn end_mission_when_leaving_path() {
+        let size = &pos(10, 10);
+        let mut tracker = AntTracker::default();
+        tracker.update(&[pos(1, 1)]);
+        tracker.set_mission(Mission::new(
+            "test",
+            path(&[pos(1, 1), pos(1, 2), pos(1, 3)]),
+        ));
+
+        tracker.record_orders(&[pos(1, 1).south()], size);
+        tracker.update(&[pos(2, 1)]);
+
+        assert_eq![tracker.id(&pos(2, 1)), Some(1)];
+        assert_eq![tracker.mission(&pos(2, 1)), None];
+    }
+}
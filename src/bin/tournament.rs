@@ -0,0 +1,383 @@
+//! Headless self-play tournament, where every pair of entrants play
+//! each other on every map and seed, in both player orders. Prints
+//! win rates, average ants alive and hills razed per entrant.
+//!
+//! Usage: tournament [--seeds N] [--turns N] [--map FILE]...
+//!                   [--replays DIR]
+//!
+//! Without any map files, a couple of built-in 2 player maps are
+//! used. With `--replays`, a replay of each game is written into
+//! given directory.
+
+extern crate ants_ai_challenge_api;
+extern crate rand;
+
+use ants_ai_challenge_api::*;
+use jockbot_ants_bot::map_file::read_map_file;
+use jockbot_ants_bot::replay::write_replay;
+use jockbot_ants_bot::simulator::*;
+use jockbot_ants_bot::strategy::*;
+use jockbot_ants_bot::world_step::distance2;
+use jockbot_ants_bot::FooAgent;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use std::collections::HashSet;
+
+const MAPS: &[(&str, &str)] = &[
+    (
+        "open",
+        "------------------------------
+         ----0-----------------*-------
+         ------------------------------
+         --------%%%%------------------
+         ------------------------------
+         -------*----------------------
+         ------------------------------
+         ------------------%%%%--------
+         ------------------------------
+         -------*-----------------1----
+         ------------------------------",
+    ),
+    (
+        "walled",
+        "------------------------------
+         ----0-----%------*----%-------
+         ----------%-----------%-------
+         ----------%%%%----%%%%%-------
+         ------------------------------
+         ---*---------------------*----
+         ------------------------------
+         -------%%%%%----%%%%----------
+         -------%-----------%----------
+         -------%----*------%-----1----
+         ------------------------------",
+    ),
+];
+
+/// Agent giving each ant a random order, avoiding visible water.
+struct RandomBot {
+    size: Position,
+    rng: StdRng,
+}
+
+impl Agent for RandomBot {
+    fn prepare(&mut self, params: GameParameters) {
+        self.size = pos(params.rows as u16, params.cols as u16);
+    }
+
+    fn make_turn(&mut self, world: WorldState, _turn: u32) -> Orders {
+        let waters: HashSet<Position> =
+            world.waters.iter().cloned().collect();
+        let dirs = [North, South, East, West];
+        let size = &self.size;
+        let rng = &mut self.rng;
+
+        world
+            .live_ants_for_player(0)
+            .iter()
+            .map(|ant| ant.order(dirs[rng.gen_range(0, 4)]))
+            .filter(|o| !waters.contains(&o.target_pos(size)))
+            .collect()
+    }
+}
+
+/// Agent sending each ant towards the nearest visible food, enemy
+/// hill or enemy ant, as the crow flies. Similar to the sample
+/// HunterBot of the game tools.
+struct HunterBot {
+    size: Position,
+}
+
+impl Agent for HunterBot {
+    fn prepare(&mut self, params: GameParameters) {
+        self.size = pos(params.rows as u16, params.cols as u16);
+    }
+
+    fn make_turn(&mut self, world: WorldState, _turn: u32) -> Orders {
+        let size = &self.size;
+        let waters: HashSet<Position> =
+            world.waters.iter().cloned().collect();
+        let mut targets: Vec<Position> = world.foods.clone();
+        for player in 1..world.hills.len().max(world.live_ants.len())
+        {
+            targets.extend(
+                world.hills.get(player).cloned().unwrap_or_default(),
+            );
+            targets.extend(world.live_ants_for_player(player as u8));
+        }
+
+        let mut occupied: HashSet<Position> =
+            world.live_ants_for_player(0).into_iter().collect();
+        let mut orders = vec![];
+
+        for ant in world.live_ants_for_player(0) {
+            let target = match targets
+                .iter()
+                .min_by_key(|t| distance2(&ant, t, size))
+            {
+                Some(target) => target,
+                None => continue,
+            };
+
+            let best = [North, South, East, West]
+                .iter()
+                .map(|&dir| ant.order(dir))
+                .filter(|o| {
+                    let next = o.target_pos(size);
+                    !waters.contains(&next)
+                        && !occupied.contains(&next)
+                })
+                .min_by_key(|o| {
+                    distance2(&o.target_pos(size), target, size)
+                });
+
+            if let Some(order) = best {
+                occupied.remove(&ant);
+                occupied.insert(order.target_pos(size));
+                orders.push(order);
+            }
+        }
+        orders
+    }
+}
+
+fn without_battle(_params: &GameParameters) -> CompositeStrategy {
+    CompositeStrategy::new(vec![
+        Box::new(HillDefender::default()),
+        Box::new(HillRaiser::default()),
+        Box::new(GatherFood::default()),
+        Box::new(SpreadOutScout::default()),
+        Box::new(RandomWalk {}),
+    ])
+}
+
+fn gatherer(_params: &GameParameters) -> CompositeStrategy {
+    CompositeStrategy::new(vec![
+        Box::new(GatherFood::default()),
+        Box::new(SpreadOutScout::default()),
+        Box::new(RandomWalk {}),
+    ])
+}
+
+struct Entrant {
+    name: &'static str,
+    create: fn(u64) -> Box<dyn Agent>,
+}
+
+const ENTRANTS: &[Entrant] = &[
+    Entrant {
+        name: "default",
+        create: |_| Box::new(FooAgent::default().quiet()),
+    },
+    Entrant {
+        name: "without_battle",
+        create: |_| {
+            Box::new(FooAgent::with_strategy(without_battle).quiet())
+        },
+    },
+    Entrant {
+        name: "gatherer",
+        create: |_| {
+            Box::new(FooAgent::with_strategy(gatherer).quiet())
+        },
+    },
+    Entrant {
+        name: "hunter_bot",
+        create: |_| Box::new(HunterBot { size: pos(0, 0) }),
+    },
+    Entrant {
+        name: "random_bot",
+        create: |seed| {
+            Box::new(RandomBot {
+                size: pos(0, 0),
+                rng: StdRng::seed_from_u64(seed),
+            })
+        },
+    },
+];
+
+#[derive(Default)]
+struct Tally {
+    games: u32,
+    wins: u32,
+    draws: u32,
+    ants_alive: usize,
+    hills_razed: u32,
+}
+
+struct Args {
+    seeds: u64,
+    turns: u32,
+    map_files: Vec<String>,
+    replay_dir: Option<String>,
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+fn parse_args() -> Args {
+    let mut result = Args {
+        seeds: 3,
+        turns: 200,
+        map_files: vec![],
+        replay_dir: None,
+    };
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    for pair in args.chunks(2) {
+        let value = pair.get(1).cloned().unwrap_or_default();
+        match (pair[0].as_str(), value.parse::<u64>().ok()) {
+            ("--seeds", Some(v)) => result.seeds = v,
+            ("--turns", Some(v)) => result.turns = v as u32,
+            ("--map", _) if !value.is_empty() => {
+                result.map_files.push(value)
+            }
+            ("--replays", _) if !value.is_empty() => {
+                result.replay_dir = Some(value)
+            }
+            _ => exit_with(concat!(
+                "Usage: tournament [--seeds N] [--turns N] ",
+                "[--map FILE]... [--replays DIR]"
+            )),
+        }
+    }
+    result
+}
+
+/// Maps from given files, or the built-in maps if no files given.
+fn load_maps(map_files: &[String]) -> Vec<(String, Map)> {
+    if map_files.is_empty() {
+        return MAPS
+            .iter()
+            .map(|(name, map)| {
+                (name.to_string(), Map::from_line_map(map))
+            })
+            .collect();
+    }
+
+    map_files
+        .iter()
+        .map(|file| match read_map_file(file) {
+            Ok(ref map) if map.players != 2 => exit_with(&format!(
+                "Map '{}' is not a 2 player map",
+                file
+            )),
+            Ok(map) => (file.clone(), map),
+            Err(message) => exit_with(&message),
+        })
+        .collect()
+}
+
+fn main() {
+    let Args {
+        seeds,
+        turns,
+        map_files,
+        replay_dir,
+    } = parse_args();
+    let maps = load_maps(&map_files);
+    let params = GameParameters {
+        attackradius2: 5,
+        viewradius2: 77,
+        spawnradius2: 1,
+        turns,
+        turntime_ms: 100,
+        loadtime_ms: 1000,
+        ..GameParameters::default()
+    };
+
+    let mut tallies: Vec<Tally> =
+        ENTRANTS.iter().map(|_| Tally::default()).collect();
+
+    let pairings: Vec<(usize, usize)> = (0..ENTRANTS.len())
+        .flat_map(|a| (0..ENTRANTS.len()).map(move |b| (a, b)))
+        .filter(|(a, b)| a != b)
+        .collect();
+
+    for (map_name, map) in maps.iter() {
+        for &(a, b) in pairings.iter() {
+            for seed in 0..seeds {
+                let mut agents = vec![
+                    (ENTRANTS[a].create)(seed),
+                    (ENTRANTS[b].create)(seed + 1),
+                ];
+                let mut game = Game::new(map, &params, seed);
+                if replay_dir.is_some() {
+                    game = game.recording_replay();
+                }
+                let result = game.play(&mut agents);
+                let winners = result.winners();
+
+                if let (Some(dir), Some(replay)) =
+                    (replay_dir.as_ref(), game.replay())
+                {
+                    let file = format!(
+                        "{}/{}_{}_vs_{}_{}.json",
+                        dir,
+                        map_name.replace('/', "_"),
+                        ENTRANTS[a].name,
+                        ENTRANTS[b].name,
+                        seed
+                    );
+                    if let Err(e) =
+                        std::fs::write(&file, write_replay(&replay))
+                    {
+                        exit_with(&format!(
+                            "Can not write replay '{}': {}",
+                            file, e
+                        ));
+                    }
+                }
+
+                for (player, &entrant) in [a, b].iter().enumerate() {
+                    let tally = &mut tallies[entrant];
+                    tally.games += 1;
+                    tally.ants_alive += result.ants_alive[player];
+                    tally.hills_razed += result.hills_razed[player];
+                    if winners.contains(&player) {
+                        if winners.len() == 1 {
+                            tally.wins += 1;
+                        } else {
+                            tally.draws += 1;
+                        }
+                    }
+                }
+
+                println!(
+                    "{:>8} seed {:>3}: {:>15} vs {:<15} scores {:?}",
+                    map_name,
+                    seed,
+                    ENTRANTS[a].name,
+                    ENTRANTS[b].name,
+                    result.scores
+                );
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{:<15} {:>6} {:>9} {:>6} {:>11} {:>12}",
+        "entrant",
+        "games",
+        "win rate",
+        "draws",
+        "ants alive",
+        "hills razed"
+    );
+    for (entrant, tally) in ENTRANTS.iter().zip(tallies.iter()) {
+        let games = f64::from(tally.games.max(1));
+        println!(
+            "{:<15} {:>6} {:>8.1}% {:>6} {:>11.1} {:>12.2}",
+            entrant.name,
+            tally.games,
+            100.0 * f64::from(tally.wins) / games,
+            tally.draws,
+            tally.ants_alive as f64 / games,
+            f64::from(tally.hills_razed) / games
+        );
+    }
+}
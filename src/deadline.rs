@@ -0,0 +1,83 @@
+use std::time::Duration;
+use std::time::Instant;
+
+/// Point in time when planning of the current turn must be finished.
+/// Strategies and searches check the deadline and stop gracefully,
+/// returning what they have found so far.
+#[derive(Debug, Clone)]
+pub struct Deadline {
+    end: Option<Instant>,
+}
+
+impl Deadline {
+    /// A deadline that never passes, e.g. for tests.
+    pub fn never() -> Deadline {
+        Deadline { end: None }
+    }
+
+    /// A deadline passing after given time budget, counted from now.
+    pub fn after(budget: Duration) -> Deadline {
+        Deadline {
+            end: Some(Instant::now() + budget),
+        }
+    }
+
+    /// Time left until deadline is passed, or None if the deadline
+    /// never passes.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.end.map(|end| {
+            let now = Instant::now();
+            if now < end {
+                end - now
+            } else {
+                Duration::from_millis(0)
+            }
+        })
+    }
+
+    /// Is there less than given margin left until deadline.
+    pub fn is_near(&self, margin: Duration) -> bool {
+        match self.remaining() {
+            Some(left) => left <= margin,
+            None => false,
+        }
+    }
+
+    pub fn is_passed(&self) -> bool {
+        self.is_near(Duration::from_millis(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn never_passed() {
+        let deadline = Deadline::never();
+
+        assert_eq!(None, deadline.remaining());
+        assert!(!deadline.is_near(Duration::from_secs(1_000)));
+        assert!(!deadline.is_passed());
+    }
+
+    #[test]
+    fn passed_when_no_time_left() {
+        let deadline = Deadline::after(Duration::from_millis(0));
+
+        assert_eq!(
+            Some(Duration::from_millis(0)),
+            deadline.remaining()
+        );
+        assert!(deadline.is_passed());
+    }
+
+    #[test]
+    fn near_when_less_than_margin_left() {
+        let deadline = Deadline::after(Duration::from_secs(1_000));
+
+        assert!(!deadline.is_passed());
+        assert!(!deadline.is_near(Duration::from_secs(1)));
+        assert!(deadline.is_near(Duration::from_secs(2_000)));
+    }
+}
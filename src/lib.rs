@@ -1,76 +1,336 @@
 use ants_ai_challenge_api::*;
 use std::collections::HashSet;
 use std::iter::FromIterator;
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[macro_use]
 pub mod utilities;
+pub mod ant_tracker;
+pub mod deadline;
+pub mod map_file;
+pub mod planner;
+pub mod replay;
+pub mod simulator;
 pub mod strategy;
+pub mod world_memory;
 pub mod world_step;
 
+use crate::ant_tracker::AntTracker;
+use crate::deadline::Deadline;
+use crate::planner::*;
+use crate::replay::ReplayRecorder;
 use crate::strategy::*;
+use crate::world_memory::*;
 use crate::world_step::*;
 
-#[derive(Default)]
+/// Share of the turn time used for planning, leaving the rest as
+/// margin for filtering orders and communicating with the game.
+const TURN_TIME_USAGE_PERCENT: u64 = 75;
+
+/// Share of the turn time after which orders planned so far are
+/// given, even when the planning worker is not done.
+const HARD_DEADLINE_PERCENT: u64 = 85;
+
+/// Length up to which the distance fields shared by the strategies
+/// are flooded each turn, the longest cutoff of the default
+/// strategies.
+const FIELD_CUTOFF_LEN: usize = 30;
+
+/// Time waited at the start of a turn for a worker still planning
+/// an earlier turn, before skipping planning of this turn.
+const LATE_WORKER_GRACE: Duration = Duration::from_millis(5);
+
+/// Creates the strategy used through a game, given the game
+/// parameters.
+pub type StrategyFactory =
+    Box<dyn Fn(&GameParameters) -> CompositeStrategy>;
+
 pub struct FooAgent {
     params: GameParameters,
-    accumulated_water: HashSet<Position>,
+    memory: WorldMemory,
+    create_strategy: StrategyFactory,
+    step_costs: StepCosts,
+    /// Strategy and tracked ants, None while lent to a worker.
+    planning: Option<Planning>,
+    /// Worker not done at the end of the previous turn.
+    worker: Option<Receiver<Planned>>,
+    /// Orders given while the worker was late, to be recorded when
+    /// its planning state is returned.
+    late_orders: Orders,
+    /// My ants and orders of the turns skipped while waiting for the
+    /// late worker, replayed on its tracker when it is done.
+    skipped_turns: Vec<(Vec<Position>, Orders)>,
+    print_world: bool,
+    replay_file: Option<String>,
+    recorder: Option<ReplayRecorder>,
+}
+
+impl Default for FooAgent {
+    fn default() -> FooAgent {
+        FooAgent::with_config(default_strategy_config())
+            .expect("known strategies")
+    }
+}
+
+impl FooAgent {
+    /// Agent using strategy from given factory, e.g. for comparing
+    /// strategy configurations against each other.
+    pub fn with_strategy(
+        create_strategy: impl Fn(&GameParameters) -> CompositeStrategy
+            + 'static,
+    ) -> FooAgent {
+        let params = GameParameters::default();
+        FooAgent {
+            planning: Some(Planning {
+                strategy: create_strategy(&params),
+                tracker: AntTracker::default(),
+            }),
+            params,
+            memory: WorldMemory::default(),
+            create_strategy: Box::new(create_strategy),
+            step_costs: StepCosts::default(),
+            worker: None,
+            late_orders: vec![],
+            skipped_turns: vec![],
+            print_world: true,
+            replay_file: None,
+            recorder: None,
+        }
+    }
+
+    /// Agent using the strategies and step costs of given config, or
+    /// an error naming the first unknown strategy of the config.
+    pub fn with_config(
+        config: StrategyConfig,
+    ) -> Result<FooAgent, String> {
+        config.create(&GameParameters::default())?;
+
+        let step_costs = config.step_costs;
+        let mut agent = FooAgent::with_strategy(move |params| {
+            config.create(params).expect("strategies checked")
+        });
+        agent.step_costs = step_costs;
+        Ok(agent)
+    }
+
+    /// Do not print the world state to stderr each turn.
+    pub fn quiet(mut self) -> FooAgent {
+        self.print_world = false;
+        self
+    }
+
+    /// Record each turn's world state and orders into given replay
+    /// file, viewable in the official HTML visualizer. The file is
+    /// written at the end of the game, see write_replay.
+    pub fn recording_replay(mut self, path: &str) -> FooAgent {
+        self.replay_file = Some(path.to_string());
+        self
+    }
+
+    /// Write the replay of the turns recorded so far, if recording.
+    /// Turns are recorded in memory and written at the end of the
+    /// game, i.e. on the last turn and when the game is over.
+    pub fn write_replay(&self) {
+        if let (Some(recorder), Some(path)) =
+            (self.recorder.as_ref(), self.replay_file.as_ref())
+        {
+            if let Err(message) = recorder.write_file(path) {
+                eprintln!("{}", message);
+            }
+        }
+    }
+
+    fn turn_time(&self, percent: u64) -> Duration {
+        Duration::from_millis(
+            self.params.turntime_ms as u64 * percent / 100,
+        )
+    }
+
+    fn fresh_planning(&self) -> Planning {
+        Planning {
+            strategy: (self.create_strategy)(&self.params),
+            tracker: AntTracker::default(),
+        }
+    }
+
+    /// Planning state, taken back from a late worker if it is done
+    /// by now. None while the worker is still busy, rather than
+    /// starting a second planning state.
+    fn take_planning(&mut self, size: &Position) -> Option<Planning> {
+        if let Some(worker) = self.worker.take() {
+            let grace = Deadline::after(LATE_WORKER_GRACE);
+            match collect(&worker, &grace) {
+                (_, Worker::Done(mut planning)) => {
+                    let tracker = &mut planning.tracker;
+                    tracker.record_orders(&self.late_orders, size);
+                    for (ants, orders) in self.skipped_turns.iter() {
+                        tracker.update(ants);
+                        tracker.record_orders(orders, size);
+                    }
+                    self.planning = Some(planning);
+                }
+                (_, Worker::Busy) => {
+                    self.worker = Some(worker);
+                    return None;
+                }
+                // State lost with the worker, started anew below.
+                (_, Worker::Gone) => {}
+            }
+            self.late_orders.clear();
+            self.skipped_turns.clear();
+        }
+
+        match self.planning.take() {
+            Some(planning) => Some(planning),
+            None => Some(self.fresh_planning()),
+        }
+    }
+
+    fn world_step(
+        &self,
+        seen_world: &WorldState,
+        size: &Position,
+    ) -> CostLayer<
+        AvoidWaterFilter<
+            AntCrashFilter<
+                VisibilityLayer<MemoryLayer<GridWorldStep>>,
+            >,
+        >,
+    > {
+        CostLayer::new(
+            AvoidWaterFilter::new(AntCrashFilter::new(
+                VisibilityLayer::new(
+                    MemoryLayer::new(
+                        GridWorldStep::new(
+                            self.memory.remembered_world(seen_world),
+                            size.clone(),
+                        ),
+                        &self.memory,
+                    ),
+                    self.params.viewradius2 as u32,
+                    self.memory.seen(),
+                ),
+            )),
+            self.params.attackradius2 as u32,
+            self.step_costs.danger_cost,
+            self.step_costs.crowd_cost,
+        )
+    }
 }
 
 fn eprint(step: &impl WorldStep) {
-    let ten_millis = std::time::Duration::from_millis(10);
-    std::thread::sleep(ten_millis);
     eprintln!("");
     eprint!("{}", step.format("    ", false));
     eprintln!("");
-    std::thread::sleep(ten_millis);
 }
 
 impl Agent for FooAgent {
     fn prepare(&mut self, params: GameParameters) {
+        if self.replay_file.is_some() {
+            self.recorder = Some(ReplayRecorder::new(&params));
+        }
         self.params = params;
+        self.planning = Some(self.fresh_planning());
+        self.worker = None;
+        self.late_orders.clear();
+        self.skipped_turns.clear();
     }
 
     fn make_turn(
         &mut self,
         world: WorldState,
-        _turn_count: u32,
+        turn_count: u32,
     ) -> Orders {
-        world.waters.iter().cloned().for_each(|w| {
-            self.accumulated_water.insert(w);
-        });
+        let deadline =
+            Deadline::after(self.turn_time(TURN_TIME_USAGE_PERCENT));
+        let hard_deadline =
+            Deadline::after(self.turn_time(HARD_DEADLINE_PERCENT));
 
-        let world = WorldState {
-            dead_ants: world.dead_ants.clone(),
-            foods: world.foods.clone(),
-            hills: world.hills.clone(),
-            live_ants: world.live_ants,
-            waters: Vec::from_iter(
-                self.accumulated_water.iter().cloned(),
-            ),
-        };
+        let size =
+            pos(self.params.rows as u16, self.params.cols as u16);
+
+        let visible = positions_within(
+            &world.live_ants_for_player(0),
+            self.params.viewradius2 as u32,
+            &size,
+        );
+        self.memory.update(&world, turn_count, &visible);
+        let seen_world = world;
 
         let mut my_ants: HashSet<Position> = HashSet::from_iter(
-            world.live_ants_for_player(0).iter().cloned(),
+            seen_world.live_ants_for_player(0).iter().cloned(),
         );
 
-        let size =
-            pos(self.params.rows as u16, self.params.cols as u16);
+        let world_step = Arc::new(FieldLayer::new(
+            self.world_step(&seen_world, &size),
+            FIELD_CUTOFF_LEN,
+            &deadline,
+        ));
+        let (planned, worker) = match self.take_planning(&size) {
+            Some(mut planning) => {
+                planning
+                    .tracker
+                    .update(&seen_world.live_ants_for_player(0));
+                let receiver = spawn_planning(
+                    planning,
+                    Arc::clone(&world_step),
+                    my_ants,
+                    deadline,
+                );
+                let (orders, worker) =
+                    collect(&receiver, &hard_deadline);
+                (orders, Some((receiver, worker)))
+            }
+            // The late worker still has the planning state, so the
+            // ants walk randomly until it is handed back.
+            None => (
+                RandomWalk {}.apply(
+                    &*world_step,
+                    &mut my_ants,
+                    &deadline,
+                ),
+                None,
+            ),
+        };
 
-        let mut world_step = AvoidWaterFilter::new(
-            AntCrashFilter::new(BasicWorldStep::new(world, size)),
+        let orders = executable_orders(
+            planned,
+            &world_step.all_my_ants(),
+            &size,
         );
 
-        let strategy = &CompositeStrategy::new_with_default();
-
-        let orders = strategy.apply(&world_step, &mut my_ants);
+        match worker {
+            Some((_, Worker::Done(mut planning))) => {
+                planning.tracker.record_orders(&orders, &size);
+                self.planning = Some(planning);
+            }
+            Some((receiver, Worker::Busy)) => {
+                self.worker = Some(receiver);
+                self.late_orders = orders.clone();
+            }
+            // Planning state started anew next turn.
+            Some((_, Worker::Gone)) => {}
+            None => self.skipped_turns.push((
+                seen_world.live_ants_for_player(0),
+                orders.clone(),
+            )),
+        }
 
-        for order in orders {
-            world_step.add_order(order.clone());
+        if self.print_world {
+            eprint(&*world_step);
         }
 
-        eprint(&world_step);
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record_turn(turn_count, &seen_world, &orders);
+            // The game may stop the bot right after the last turn.
+            if turn_count == self.params.turns {
+                self.write_replay();
+            }
+        }
 
-        world_step.get_orders()
+        orders
     }
 }
 
@@ -116,4 +376,184 @@ mod tests {
             "should not walk into water"
         );
     }
+
+    #[test]
+    fn plan_with_configured_step_costs() {
+        let config =
+            parse_strategy_config("step_costs danger_cost=7")
+                .unwrap();
+        let mut agent = FooAgent::with_config(config)
+            .expect("known strategies")
+            .quiet();
+        agent.prepare(GameParameters {
+            cols: 5,
+            rows: 1,
+            attackradius2: 1,
+            ..GameParameters::default()
+        });
+
+        let world = utilities::world("a--b-");
+        let world_step = agent.world_step(&world, &pos(1, 5));
+
+        assert_eq!(world_step.step_cost(&pos(0, 1)), 1);
+        assert_eq!(world_step.step_cost(&pos(0, 2)), 8);
+    }
+
+    #[test]
+    fn use_strategy_from_factory() {
+        fn idle(_params: &GameParameters) -> CompositeStrategy {
+            CompositeStrategy::new(vec![])
+        }
+        let mut agent = FooAgent::with_strategy(idle).quiet();
+
+        agent.prepare(GameParameters {
+            cols: 5,
+            rows: 1,
+            turntime_ms: 10,
+            ..GameParameters::default()
+        });
+
+        let orders = agent.make_turn(utilities::world("-a*--"), 1);
+
+        assert_eq!(orders, vec![]);
+    }
+
+    #[test]
+    fn give_ready_orders_when_strategy_is_slow() {
+        struct Sleepy {}
+        impl Strategy for Sleepy {
+            fn apply(
+                &mut self,
+                _world_step: &dyn WorldStep,
+                _ants_available: &mut HashSet<Position>,
+                _deadline: &Deadline,
+            ) -> Orders {
+                std::thread::sleep(Duration::from_millis(300));
+                vec![]
+            }
+        }
+        fn slow(_params: &GameParameters) -> CompositeStrategy {
+            CompositeStrategy::new(vec![
+                Box::new(GatherFood::default()),
+                Box::new(Sleepy {}),
+            ])
+        }
+        let mut agent = FooAgent::with_strategy(slow).quiet();
+
+        agent.prepare(GameParameters {
+            cols: 5,
+            rows: 1,
+            viewradius2: 4,
+            turntime_ms: 100,
+            ..GameParameters::default()
+        });
+        let started = std::time::Instant::now();
+        let orders = agent.make_turn(utilities::world("-a-*-"), 1);
+
+        // Well before the slow strategy is done.
+        assert!(started.elapsed() < Duration::from_millis(250));
+        assert_eq!(orders, vec![pos(0, 1).east()]);
+    }
+
+    #[test]
+    fn wait_for_state_of_late_worker() {
+        struct Sleepy {}
+        impl Strategy for Sleepy {
+            fn apply(
+                &mut self,
+                _world_step: &dyn WorldStep,
+                _ants_available: &mut HashSet<Position>,
+                _deadline: &Deadline,
+            ) -> Orders {
+                std::thread::sleep(Duration::from_millis(200));
+                vec![]
+            }
+        }
+        fn slow(_params: &GameParameters) -> CompositeStrategy {
+            CompositeStrategy::new(vec![Box::new(Sleepy {})])
+        }
+        const MAP: &str = "---
+                           -a-
+                           ---";
+        let mut agent = FooAgent::with_strategy(slow).quiet();
+
+        agent.prepare(GameParameters {
+            cols: 3,
+            rows: 3,
+            viewradius2: 4,
+            turntime_ms: 20,
+            ..GameParameters::default()
+        });
+        agent.make_turn(utilities::world(MAP), 1);
+        let orders = agent.make_turn(utilities::world(MAP), 2);
+
+        // Walking randomly, without a second planning state.
+        assert_eq!(orders.len(), 1);
+        assert!(agent.planning.is_none());
+        assert!(agent.worker.is_some());
+        assert_eq!(agent.skipped_turns.len(), 1);
+
+        std::thread::sleep(Duration::from_millis(300));
+        agent.make_turn(utilities::world(MAP), 3);
+
+        assert!(agent.skipped_turns.is_empty());
+    }
+
+    #[test]
+    fn record_replay_of_turns() {
+        let path = std::env::temp_dir()
+            .join("jockbot_record_replay_of_turns.json");
+        let path = path.to_str().unwrap();
+        let mut agent =
+            FooAgent::default().quiet().recording_replay(path);
+
+        agent.prepare(GameParameters {
+            cols: 5,
+            rows: 1,
+            viewradius2: 4,
+            turntime_ms: 10,
+            turns: 1,
+            ..GameParameters::default()
+        });
+        let orders = agent.make_turn(utilities::world("-a*--"), 1);
+        let replay = crate::replay::read_replay_file(path)
+            .expect("replay written");
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(replay.ants.len(), 1);
+        assert_eq!(replay.ants[0].moves.len(), 1);
+        assert_eq!(orders.is_empty(), replay.ants[0].moves == "-");
+        assert_eq!(
+            replay.world_state(1, 0),
+            utilities::world("-a*--")
+        );
+    }
+
+    #[test]
+    fn write_replay_at_end_of_game_only() {
+        let path = std::env::temp_dir()
+            .join("jockbot_write_replay_at_end_of_game_only.json");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        let mut agent =
+            FooAgent::default().quiet().recording_replay(path);
+
+        agent.prepare(GameParameters {
+            cols: 5,
+            rows: 1,
+            viewradius2: 4,
+            turntime_ms: 10,
+            turns: 100,
+            ..GameParameters::default()
+        });
+        agent.make_turn(utilities::world("-a*--"), 1);
+        let written_early = std::path::Path::new(path).exists();
+        agent.write_replay();
+        let replay = crate::replay::read_replay_file(path)
+            .expect("replay written");
+        std::fs::remove_file(path).unwrap();
+
+        assert!(!written_early);
+        assert_eq!(replay.ants[0].moves.len(), 1);
+    }
 }
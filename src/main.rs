@@ -2,9 +2,51 @@ extern crate ants_ai_challenge_api;
 extern crate rand;
 
 use ants_ai_challenge_api::run_game;
+use jockbot_ants_bot::strategy::read_strategy_config;
 use jockbot_ants_bot::FooAgent;
 
+const USAGE: &str =
+    "Usage: jockbot_ants_bot [--config FILE] [--replay FILE]";
+
+/// Print message to stderr and exit with failure.
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+/// Usage: jockbot_ants_bot [--config FILE] [--replay FILE]
+///
+/// With `--config`, the strategies of given config file are used,
+/// see `strategies.conf`. With `--replay`, each turn's world state
+/// and orders are recorded into given replay file, written when the
+/// game is over.
 fn main() {
-    let mut agent = FooAgent::default();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut config_file = None;
+    let mut replay_file = None;
+
+    for pair in args.chunks(2) {
+        match pair {
+            [flag, file] if flag == "--config" => {
+                config_file = Some(file)
+            }
+            [flag, file] if flag == "--replay" => {
+                replay_file = Some(file)
+            }
+            _ => exit_with(USAGE),
+        }
+    }
+
+    let mut agent = match config_file.map(|f| {
+        read_strategy_config(f).and_then(FooAgent::with_config)
+    }) {
+        None => FooAgent::default(),
+        Some(Ok(agent)) => agent,
+        Some(Err(message)) => exit_with(&message),
+    };
+    if let Some(file) = replay_file {
+        agent = agent.recording_replay(file);
+    }
     run_game(&mut agent);
+    agent.write_replay();
 }
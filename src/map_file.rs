@@ -0,0 +1,217 @@
+use crate::simulator::Map;
+use ants_ai_challenge_api::*;
+use std::collections::HashMap;
+
+/// Parse the official Ants map format, e.g.
+///
+/// ```text
+/// rows 2
+/// cols 4
+/// players 2
+/// m a.%1
+/// m 0*.b
+/// ```
+///
+/// where `.` is land, `%` water, `*` food, `0`-`9` hills, `a`-`j`
+/// ants and `A`-`J` ants on their own hill.
+pub fn parse_map(text: &str) -> Result<Map, String> {
+    let mut header = HashMap::<&str, usize>::new();
+    let mut map_lines = vec![];
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let mut parts = line.splitn(2, ' ');
+        let key = parts.next().unwrap_or("");
+        let value = parts.next().unwrap_or("").trim();
+
+        match key {
+            "" => {}
+            "m" => map_lines.push(value),
+            "rows" | "cols" | "players" => {
+                let number = value.parse().map_err(|_| {
+                    format!(
+                        "Line {}: bad number '{}'",
+                        index + 1,
+                        value
+                    )
+                })?;
+                header.insert(key, number);
+            }
+            _ => {
+                return Err(format!(
+                    "Line {}: unknown line '{}'",
+                    index + 1,
+                    line
+                ))
+            }
+        }
+    }
+
+    let get = |key| {
+        header
+            .get(key)
+            .cloned()
+            .ok_or_else(|| format!("Missing '{}'", key))
+    };
+    let rows = get("rows")?;
+    let cols = get("cols")?;
+    let players = get("players")?;
+
+    if map_lines.len() != rows {
+        return Err(format!(
+            "Expected {} map lines, found {}",
+            rows,
+            map_lines.len()
+        ));
+    }
+
+    let mut world = WorldState::default();
+    for (row, line) in map_lines.iter().enumerate() {
+        if line.chars().count() != cols {
+            return Err(format!(
+                "Map line {} is not {} long",
+                row, cols
+            ));
+        }
+
+        for (col, ch) in line.chars().enumerate() {
+            let p = pos(row as u16, col as u16);
+            let owner =
+                |first: char| (ch as u8 - first as u8) as usize;
+
+            world = match ch {
+                '.' => world,
+                '%' => world.water(p),
+                '*' => world.food(p),
+                '0'..='9' if owner('0') < players => {
+                    world.hill(p, owner('0') as u8)
+                }
+                'a'..='j' if owner('a') < players => {
+                    world.live_ant(p, owner('a') as u8)
+                }
+                'A'..='J' if owner('A') < players => world
+                    .live_ant(p.clone(), owner('A') as u8)
+                    .hill(p, owner('A') as u8),
+                _ => {
+                    return Err(format!(
+                        "Bad map character '{}' at row {}, col {}",
+                        ch, row, col
+                    ))
+                }
+            };
+        }
+    }
+
+    Ok(Map {
+        world,
+        size: pos(rows as u16, cols as u16),
+        players,
+    })
+}
+
+/// Read and parse map file.
+pub fn read_map_file(path: &str) -> Result<Map, String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| format!("Can not read map '{}': {}", path, e))
+        .and_then(|text| parse_map(&text))
+}
+
+/// Write map in the official Ants map format, see `parse_map`.
+pub fn write_map(map: &Map) -> String {
+    let mut tiles = HashMap::<Position, char>::new();
+    let offset = |first: char, owner: usize| {
+        (first as u8 + owner as u8) as char
+    };
+
+    for water in map.world.waters.iter() {
+        tiles.insert(water.clone(), '%');
+    }
+    for food in map.world.foods.iter() {
+        tiles.insert(food.clone(), '*');
+    }
+    for (owner, hills) in map.world.hills.iter().enumerate() {
+        for hill in hills {
+            tiles.insert(hill.clone(), offset('0', owner));
+        }
+    }
+    for (owner, ants) in map.world.live_ants.iter().enumerate() {
+        for ant in ants {
+            let on_hill = tiles.get(ant) == Some(&offset('0', owner));
+            let first = if on_hill { 'A' } else { 'a' };
+            tiles.insert(ant.clone(), offset(first, owner));
+        }
+    }
+
+    let mut result = format!(
+        "rows {}\ncols {}\nplayers {}\n",
+        map.size.row, map.size.col, map.players
+    );
+    for row in 0..map.size.row {
+        result += "m ";
+        for col in 0..map.size.col {
+            let tile =
+                tiles.get(&pos(row, col)).cloned().unwrap_or('.');
+            result.push(tile);
+        }
+        result += "\n";
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = "rows 3
+cols 5
+players 2
+m a.%1.
+m 0*..b
+m ..B*%
+";
+
+    #[test]
+    fn parse_official_map() {
+        let map = parse_map(MAP).expect("valid map");
+
+        assert_eq!(map.size, pos(3, 5));
+        assert_eq!(map.players, 2);
+        assert_eq!(map.world.waters, vec![pos(0, 2), pos(2, 4)]);
+        assert_eq!(map.world.foods, vec![pos(1, 1), pos(2, 3)]);
+        assert_eq!(map.world.hills[0], vec![pos(1, 0)]);
+        assert_eq!(map.world.hills[1], vec![pos(0, 3), pos(2, 2)]);
+        assert_eq!(
+            map.world.live_ants_for_player(0),
+            vec![pos(0, 0)]
+        );
+        assert_eq!(
+            map.world.live_ants_for_player(1),
+            vec![pos(1, 4), pos(2, 2)]
+        );
+    }
+
+    #[test]
+    fn write_parsed_map() {
+        let map = parse_map(MAP).expect("valid map");
+
+        assert_eq!(write_map(&map), MAP);
+    }
+
+    #[test]
+    fn report_bad_maps() {
+        assert_eq!(
+            parse_map("rows 1\ncols 2\nm ..").map(|m| m.players),
+            Err("Missing 'players'".to_string())
+        );
+        assert_eq!(
+            parse_map("rows 1\ncols 2\nplayers 1\nm ...")
+                .map(|m| m.players),
+            Err("Map line 0 is not 2 long".to_string())
+        );
+        assert_eq!(
+            parse_map("rows 1\ncols 2\nplayers 1\nm .b")
+                .map(|m| m.players),
+            Err("Bad map character 'b' at row 0, col 1".to_string())
+        );
+    }
+}
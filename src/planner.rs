@@ -0,0 +1,179 @@
+use crate::ant_tracker::AntTracker;
+use crate::deadline::Deadline;
+use crate::strategy::*;
+use crate::world_step::WorldStep;
+use ants_ai_challenge_api::*;
+
+use std::collections::HashSet;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+
+/// State kept through a game by the planning worker, handed back
+/// when planning of a turn is done.
+pub struct Planning {
+    pub strategy: CompositeStrategy,
+    pub tracker: AntTracker,
+}
+
+/// Message from the planning worker.
+pub enum Planned {
+    /// Orders of one strategy, ready to be given.
+    Orders(Orders),
+    /// All strategies are applied.
+    Done(Planning),
+}
+
+/// Plan orders of given ants on a worker thread, sending the orders
+/// of each strategy as soon as they are ready, so that a slow
+/// strategy never makes us miss the turn, see `collect`. The world
+/// step is shared with the caller, e.g. for filtering the orders.
+pub fn spawn_planning<W: WorldStep + Send + 'static>(
+    mut planning: Planning,
+    world_step: Arc<W>,
+    mut ants_available: HashSet<Position>,
+    deadline: Deadline,
+) -> Receiver<Planned> {
+    let (sender, receiver) = channel();
+
+    thread::spawn(move || {
+        planning.strategy.apply_reporting(
+            &*world_step,
+            &mut ants_available,
+            &mut planning.tracker,
+            &deadline,
+            &mut |orders| {
+                if !orders.is_empty() {
+                    // Nobody listens after a missed turn.
+                    let _ =
+                        sender.send(Planned::Orders(orders.to_vec()));
+                }
+            },
+        );
+        let _ = sender.send(Planned::Done(planning));
+    });
+
+    receiver
+}
+
+/// State of the worker when collecting its orders.
+pub enum Worker {
+    /// All strategies are applied, handing back the planning state.
+    Done(Planning),
+    /// Still planning.
+    Busy,
+    /// Ended without handing back the planning state, e.g. after a
+    /// panicking strategy.
+    Gone,
+}
+
+/// Collect orders planned by the worker until it is done or the
+/// deadline is passed, whichever comes first. The planning state is
+/// returned only when the worker is done.
+pub fn collect(
+    receiver: &Receiver<Planned>,
+    deadline: &Deadline,
+) -> (Orders, Worker) {
+    let mut orders = vec![];
+
+    loop {
+        let planned = match deadline.remaining() {
+            Some(left) => match receiver.recv_timeout(left) {
+                Ok(planned) => planned,
+                Err(RecvTimeoutError::Timeout) => {
+                    return (orders, Worker::Busy)
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return (orders, Worker::Gone)
+                }
+            },
+            None => match receiver.recv() {
+                Ok(planned) => planned,
+                Err(_) => return (orders, Worker::Gone),
+            },
+        };
+
+        match planned {
+            Planned::Orders(more) => orders.extend(more),
+            Planned::Done(planning) => {
+                return (orders, Worker::Done(planning))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world_step::*;
+    use std::time::Duration;
+
+    /// Gives no orders, but takes its time doing so.
+    struct Sleepy {
+        sleep: Duration,
+    }
+
+    impl Strategy for Sleepy {
+        fn apply(
+            &mut self,
+            _world_step: &dyn WorldStep,
+            _ants_available: &mut HashSet<Position>,
+            _deadline: &Deadline,
+        ) -> Orders {
+            thread::sleep(self.sleep);
+            vec![]
+        }
+    }
+
+    fn planning(strategies: Vec<Box<dyn Strategy>>) -> Planning {
+        Planning {
+            strategy: CompositeStrategy::new(strategies),
+            tracker: AntTracker::default(),
+        }
+    }
+
+    #[test]
+    fn collect_all_orders_when_done_in_time() {
+        let world_step =
+            Arc::new(BasicWorldStep::new_from_line_map("-a-*-"));
+        let receiver = spawn_planning(
+            planning(vec![
+                Box::new(GatherFood::default()),
+                Box::new(RandomWalk {}),
+            ]),
+            world_step,
+            set![pos(0, 1)],
+            Deadline::never(),
+        );
+
+        let (orders, worker) = collect(&receiver, &Deadline::never());
+
+        assert_eq![orders.len(), 1];
+        assert![matches!(worker, Worker::Done(_))];
+    }
+
+    #[test]
+    fn return_orders_ready_at_deadline() {
+        let world_step =
+            Arc::new(BasicWorldStep::new_from_line_map("-a---"));
+        let receiver = spawn_planning(
+            planning(vec![
+                Box::new(RandomWalk {}),
+                Box::new(Sleepy {
+                    sleep: Duration::from_millis(300),
+                }),
+            ]),
+            world_step,
+            set![pos(0, 1)],
+            Deadline::never(),
+        );
+
+        let (orders, worker) = collect(
+            &receiver,
+            &Deadline::after(Duration::from_millis(100)),
+        );
+
+        assert_eq![orders.len(), 1];
+        assert![matches!(worker, Worker::Busy)];
+    }
+}
@@ -0,0 +1,825 @@
+use crate::world_step::positions_within;
+use ants_ai_challenge_api::*;
+use serde_json::json;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+/// Ant in a replay. The ant appears as food at `start_turn`, becomes
+/// an ant at `conversion_turn` and dies at `end_turn`. Each move is
+/// one of the characters `nsew-`, one for each turn alive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayAnt {
+    pub start: Position,
+    pub start_turn: u32,
+    pub conversion_turn: u32,
+    pub end_turn: u32,
+    pub owner: usize,
+    pub moves: String,
+}
+
+/// Food in a replay never converted into an ant, existing from
+/// `start_turn` until `end_turn`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayFood {
+    pub pos: Position,
+    pub start_turn: u32,
+    pub end_turn: u32,
+}
+
+/// Hill in a replay, razed or game over at `end_turn`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayHill {
+    pub pos: Position,
+    pub owner: usize,
+    pub end_turn: u32,
+}
+
+/// A complete game, as recorded in the JSON replay files of the game
+/// engine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub params: GameParameters,
+    pub players: usize,
+    pub waters: Vec<Position>,
+    pub ants: Vec<ReplayAnt>,
+    pub foods: Vec<ReplayFood>,
+    pub hills: Vec<ReplayHill>,
+}
+
+fn move_of(dir: Direction) -> char {
+    match dir {
+        North => 'n',
+        South => 's',
+        East => 'e',
+        West => 'w',
+    }
+}
+
+fn direction_of(ch: char) -> Option<Direction> {
+    match ch {
+        'n' => Some(North),
+        's' => Some(South),
+        'e' => Some(East),
+        'w' => Some(West),
+        _ => None,
+    }
+}
+
+impl ReplayAnt {
+    fn is_alive(&self, turn: u32) -> bool {
+        self.conversion_turn <= turn && turn < self.end_turn
+    }
+
+    fn is_food(&self, turn: u32) -> bool {
+        self.start_turn <= turn && turn < self.conversion_turn
+    }
+
+    /// Position at start of given turn, after all earlier moves.
+    fn position_at(&self, turn: u32, size: &Position) -> Position {
+        let move_count = turn.saturating_sub(self.conversion_turn);
+
+        self.moves
+            .chars()
+            .take(move_count as usize)
+            .flat_map(direction_of)
+            .fold(self.start.clone(), |p, dir| {
+                p.order(dir).target_pos(size)
+            })
+    }
+}
+
+impl Replay {
+    pub fn size(&self) -> Position {
+        pos(self.params.rows as u16, self.params.cols as u16)
+    }
+
+    /// Positions visible to given player at the start of given turn,
+    /// within view radius of the player's own ants.
+    fn visible(&self, turn: u32, player: usize) -> HashSet<Position> {
+        let size = &self.size();
+        let own_ants: Vec<Position> = self
+            .ants
+            .iter()
+            .filter(|a| a.owner == player && a.is_alive(turn))
+            .map(|a| a.position_at(turn, size))
+            .collect();
+        positions_within(&own_ants, self.params.viewradius2, size)
+    }
+
+    fn hills_at(&self, turn: u32) -> Vec<(Position, usize)> {
+        self.hills
+            .iter()
+            .filter(|h| turn < h.end_turn)
+            .map(|h| (h.pos.clone(), h.owner))
+            .collect()
+    }
+
+    fn live_ants_at(&self, turn: u32) -> Vec<(Position, usize)> {
+        let size = &self.size();
+        self.ants
+            .iter()
+            .filter(|a| a.is_alive(turn))
+            .map(|a| (a.position_at(turn, size), a.owner))
+            .collect()
+    }
+
+    fn dead_ants_at(&self, turn: u32) -> Vec<(Position, usize)> {
+        let size = &self.size();
+        self.ants
+            .iter()
+            .filter(|a| {
+                a.end_turn == turn && a.conversion_turn < turn
+            })
+            .map(|a| (a.position_at(turn, size), a.owner))
+            .collect()
+    }
+
+    /// Owners as numbered for given player up to given turn: the
+    /// player itself is zero and enemies are numbered in the order
+    /// the player first sees one of their hills or ants, like the
+    /// game engine does. Enemies first seen on the same turn are
+    /// numbered in the order of their positions.
+    fn owner_numbers(
+        &self,
+        turn: u32,
+        player: usize,
+    ) -> HashMap<usize, u8> {
+        let mut numbers = HashMap::new();
+        numbers.insert(player, 0);
+
+        for t in 0..=turn {
+            if numbers.len() == self.players {
+                break;
+            }
+            let visible = self.visible(t, player);
+            let mut seen: Vec<(Position, usize)> = self
+                .hills_at(t)
+                .into_iter()
+                .chain(self.live_ants_at(t))
+                .chain(self.dead_ants_at(t))
+                .filter(|(p, _)| visible.contains(p))
+                .collect();
+            seen.sort();
+            for (_, owner) in seen {
+                let next = numbers.len() as u8;
+                numbers.entry(owner).or_insert(next);
+            }
+        }
+        numbers
+    }
+
+    /// The world state at the start of given turn, as sent to given
+    /// player. Only positions within view radius of the player's own
+    /// ants are included and owners are relative to the player, see
+    /// `owner_numbers`.
+    pub fn world_state(
+        &self,
+        turn: u32,
+        player: usize,
+    ) -> WorldState {
+        let visible = self.visible(turn, player);
+        let numbers = self.owner_numbers(turn, player);
+        let relative = |entries: Vec<(Position, usize)>| {
+            let mut result: Vec<(Position, u8)> = entries
+                .into_iter()
+                .filter(|(p, _)| visible.contains(p))
+                .map(|(p, owner)| (p, numbers[&owner]))
+                .collect();
+            result.sort();
+            result
+        };
+
+        let mut waters = self.waters.clone();
+        let mut foods: Vec<Position> = self
+            .foods
+            .iter()
+            .filter(|f| f.start_turn <= turn && turn < f.end_turn)
+            .map(|f| f.pos.clone())
+            .chain(
+                self.ants
+                    .iter()
+                    .filter(|a| a.is_food(turn))
+                    .map(|a| a.start.clone()),
+            )
+            .collect();
+        waters.sort();
+        foods.sort();
+
+        let mut world = WorldState::default();
+        for p in waters.into_iter().filter(|p| visible.contains(p)) {
+            world = world.water(p);
+        }
+        for p in foods.into_iter().filter(|p| visible.contains(p)) {
+            world = world.food(p);
+        }
+        for (p, owner) in relative(self.hills_at(turn)) {
+            world = world.hill(p, owner);
+        }
+        for (p, owner) in relative(self.live_ants_at(turn)) {
+            world = world.live_ant(p, owner);
+        }
+        for (p, owner) in relative(self.dead_ants_at(turn)) {
+            world = world.dead_ant(p, owner);
+        }
+        world
+    }
+}
+
+fn field<'a>(
+    value: &'a Value,
+    key: &str,
+) -> Result<&'a Value, String> {
+    value.get(key).ok_or_else(|| format!("Missing '{}'", key))
+}
+
+fn number(value: &Value, what: &str) -> Result<u64, String> {
+    value
+        .as_u64()
+        .ok_or_else(|| format!("Expected number for {}", what))
+}
+
+fn number_field(value: &Value, key: &str) -> Result<u64, String> {
+    number(field(value, key)?, key)
+}
+
+/// Replay entries, e.g. ants, as arrays of values.
+fn entries<'a>(
+    value: &'a Value,
+    key: &str,
+) -> Result<Vec<&'a Vec<Value>>, String> {
+    match field(value, key)?.as_array() {
+        Some(entries) => entries
+            .iter()
+            .map(|e| {
+                e.as_array().ok_or_else(|| {
+                    format!("Expected array in {}", key)
+                })
+            })
+            .collect(),
+        None => Err(format!("Expected array for {}", key)),
+    }
+}
+
+/// Number at given index of replay entry.
+fn at(
+    entry: &[Value],
+    index: usize,
+    what: &str,
+) -> Result<u64, String> {
+    match entry.get(index) {
+        Some(value) => number(value, what),
+        None => Err(format!("Too short entry in {}", what)),
+    }
+}
+
+fn position_at(
+    entry: &[Value],
+    what: &str,
+) -> Result<Position, String> {
+    Ok(pos(at(entry, 0, what)? as u16, at(entry, 1, what)? as u16))
+}
+
+/// Parse JSON replay, either the complete replay file or only the
+/// contained `replaydata`.
+pub fn parse_replay(json: &str) -> Result<Replay, String> {
+    let root: Value = serde_json::from_str(json)
+        .map_err(|e| format!("Invalid JSON: {}", e))?;
+    let data = root.get("replaydata").unwrap_or(&root);
+
+    let map = field(data, "map")?;
+    let rows = number_field(map, "rows")?;
+    let cols = number_field(map, "cols")?;
+
+    let mut waters = vec![];
+    let lines = field(map, "data")?
+        .as_array()
+        .ok_or_else(|| "Expected array for map data".to_string())?;
+    for (row, line) in lines.iter().enumerate() {
+        let line = line.as_str().ok_or_else(|| {
+            "Expected string in map data".to_string()
+        })?;
+        for (col, ch) in line.chars().enumerate() {
+            if ch == '%' {
+                waters.push(pos(row as u16, col as u16));
+            }
+        }
+    }
+
+    let ants = entries(data, "ants")?
+        .into_iter()
+        .map(|e| {
+            Ok(ReplayAnt {
+                start: position_at(e, "ants")?,
+                start_turn: at(e, 2, "ants")? as u32,
+                conversion_turn: at(e, 3, "ants")? as u32,
+                end_turn: at(e, 4, "ants")? as u32,
+                owner: at(e, 5, "ants")? as usize,
+                moves: e
+                    .get(6)
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+            })
+        })
+        .collect::<Result<Vec<ReplayAnt>, String>>()?;
+
+    let foods = entries(data, "food")?
+        .into_iter()
+        .map(|e| {
+            Ok(ReplayFood {
+                pos: position_at(e, "food")?,
+                start_turn: at(e, 2, "food")? as u32,
+                end_turn: at(e, 3, "food")? as u32,
+            })
+        })
+        .collect::<Result<Vec<ReplayFood>, String>>()?;
+
+    let hills = entries(data, "hills")?
+        .into_iter()
+        .map(|e| {
+            Ok(ReplayHill {
+                pos: position_at(e, "hills")?,
+                owner: at(e, 2, "hills")? as usize,
+                end_turn: at(e, 3, "hills")? as u32,
+            })
+        })
+        .collect::<Result<Vec<ReplayHill>, String>>()?;
+
+    let optional =
+        |key| data.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+
+    Ok(Replay {
+        params: GameParameters {
+            rows: rows as _,
+            cols: cols as _,
+            turns: optional("turns") as _,
+            turntime_ms: optional("turntime") as _,
+            loadtime_ms: optional("loadtime") as _,
+            viewradius2: optional("viewradius2") as _,
+            attackradius2: optional("attackradius2") as _,
+            spawnradius2: optional("spawnradius2") as _,
+            player_seed: 0,
+        },
+        players: number_field(data, "players")? as usize,
+        waters,
+        ants,
+        foods,
+        hills,
+    })
+}
+
+/// Read and parse JSON replay file.
+pub fn read_replay_file(path: &str) -> Result<Replay, String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| format!("Can not read replay '{}': {}", path, e))
+        .and_then(|json| parse_replay(&json))
+}
+
+/// Write replay in the JSON replay format of the game engine, see
+/// `parse_replay`, viewable in the official HTML visualizer.
+pub fn write_replay(replay: &Replay) -> String {
+    let size = replay.size();
+    let waters: HashSet<&Position> = replay.waters.iter().collect();
+    let turns = replay
+        .ants
+        .iter()
+        .map(|a| a.end_turn)
+        .chain(replay.foods.iter().map(|f| f.end_turn))
+        .chain(replay.hills.iter().map(|h| h.end_turn))
+        .max()
+        .unwrap_or(0);
+
+    let map_data: Vec<String> = (0..size.row)
+        .map(|row| {
+            (0..size.col)
+                .map(|col| {
+                    if waters.contains(&pos(row, col)) {
+                        '%'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    let ants: Vec<Value> = replay
+        .ants
+        .iter()
+        .map(|a| {
+            json!([
+                a.start.row,
+                a.start.col,
+                a.start_turn,
+                a.conversion_turn,
+                a.end_turn,
+                a.owner,
+                a.moves
+            ])
+        })
+        .collect();
+    let foods: Vec<Value> = replay
+        .foods
+        .iter()
+        .map(|f| {
+            json!([f.pos.row, f.pos.col, f.start_turn, f.end_turn])
+        })
+        .collect();
+    let hills: Vec<Value> = replay
+        .hills
+        .iter()
+        .map(|h| json!([h.pos.row, h.pos.col, h.owner, h.end_turn]))
+        .collect();
+    let params = &replay.params;
+
+    json!({
+        "challenge": "ants",
+        "replayformat": "json",
+        "playernames": (0..replay.players)
+            .map(|p| format!("player {}", p))
+            .collect::<Vec<String>>(),
+        "replaydata": {
+            "revision": 3,
+            "players": replay.players,
+            "turns": params.turns,
+            "turntime": params.turntime_ms,
+            "loadtime": params.loadtime_ms,
+            "viewradius2": params.viewradius2,
+            "attackradius2": params.attackradius2,
+            "spawnradius2": params.spawnradius2,
+            "map": {
+                "rows": size.row,
+                "cols": size.col,
+                "data": map_data
+            },
+            "ants": ants,
+            "food": foods,
+            "hills": hills,
+            "scores": vec![vec![0; turns as usize + 1]; replay.players]
+        }
+    })
+    .to_string()
+}
+
+/// Records the world state and orders of each turn, as a replay.
+/// Ants are followed between turns by their orders, or for ants
+/// without orders, e.g. enemy ants, by their neighbourhood. Ants
+/// lost from sight show up as dead the turn after last seen.
+pub struct ReplayRecorder {
+    params: GameParameters,
+    size: Position,
+    waters: HashSet<Position>,
+    ants: Vec<ReplayAnt>,
+    foods: Vec<ReplayFood>,
+    hills: HashMap<(Position, usize), u32>,
+    /// Index into `ants` and retroactive move of ants expected at
+    /// position next turn.
+    expected: HashMap<(Position, usize), (usize, Option<Direction>)>,
+}
+
+impl ReplayRecorder {
+    pub fn new(params: &GameParameters) -> ReplayRecorder {
+        ReplayRecorder {
+            params: params.clone(),
+            size: pos(params.rows as u16, params.cols as u16),
+            waters: HashSet::new(),
+            ants: vec![],
+            foods: vec![],
+            hills: HashMap::new(),
+            expected: HashMap::new(),
+        }
+    }
+
+    /// Record world state at start of turn and orders given.
+    pub fn record_turn(
+        &mut self,
+        turn: u32,
+        world: &WorldState,
+        orders: &[Order],
+    ) {
+        let moves: HashMap<&Position, Direction> =
+            orders.iter().map(|o| (&o.pos, o.dir)).collect();
+
+        self.waters.extend(world.waters.iter().cloned());
+
+        for food in world.foods.iter() {
+            self.foods.push(ReplayFood {
+                pos: food.clone(),
+                start_turn: turn,
+                end_turn: turn + 1,
+            });
+        }
+
+        for (owner, hills) in world.hills.iter().enumerate() {
+            for hill in hills {
+                self.hills.insert((hill.clone(), owner), turn + 1);
+            }
+        }
+
+        let mut expected = HashMap::new();
+        let mut continued = HashSet::new();
+        std::mem::swap(&mut expected, &mut self.expected);
+        let mut find = |ant: &Position, owner: usize| match expected
+            .remove(&(ant.clone(), owner))
+        {
+            Some((index, dir)) if continued.insert(index) => {
+                Some((index, dir))
+            }
+            _ => None,
+        };
+
+        let mut live = vec![];
+        for (owner, ants) in world.live_ants.iter().enumerate() {
+            for ant in ants {
+                let index = match find(ant, owner) {
+                    Some((index, dir)) => {
+                        self.continue_ant(index, dir, turn + 1);
+                        index
+                    }
+                    None => {
+                        self.ants.push(ReplayAnt {
+                            start: ant.clone(),
+                            start_turn: turn,
+                            conversion_turn: turn,
+                            end_turn: turn + 1,
+                            owner,
+                            moves: String::new(),
+                        });
+                        self.ants.len() - 1
+                    }
+                };
+                let dir = moves.get(ant).cloned();
+                let ant_move = dir.map_or('-', move_of);
+                self.ants[index].moves.push(ant_move);
+                live.push((ant, owner, index, dir));
+            }
+        }
+
+        // Ants died this turn end here. Otherwise recorded as having
+        // died from the previous turn, staying in place.
+        for (owner, ants) in world.dead_ants.iter().enumerate() {
+            for ant in ants {
+                if let Some((index, dir)) = find(ant, owner) {
+                    self.continue_ant(index, dir, turn);
+                    continue;
+                }
+                self.ants.push(ReplayAnt {
+                    start: ant.clone(),
+                    start_turn: turn.saturating_sub(1),
+                    conversion_turn: turn.saturating_sub(1),
+                    end_turn: turn,
+                    owner,
+                    moves: "-".to_string(),
+                });
+            }
+        }
+
+        for &(ant, owner, index, dir) in live.iter() {
+            let next = dir.map_or(ant.clone(), |d| {
+                ant.order(d).target_pos(&self.size)
+            });
+            self.expected.insert((next, owner), (index, None));
+        }
+        for &(ant, owner, index, dir) in live.iter() {
+            if dir.is_some() {
+                continue;
+            }
+            for &d in [North, South, East, West].iter() {
+                let next = ant.order(d).target_pos(&self.size);
+                self.expected
+                    .entry((next, owner))
+                    .or_insert((index, Some(d)));
+            }
+        }
+    }
+
+    /// Continue ant until given end turn, replacing its last move
+    /// with given one, if any.
+    fn continue_ant(
+        &mut self,
+        index: usize,
+        dir: Option<Direction>,
+        end_turn: u32,
+    ) {
+        let ant = &mut self.ants[index];
+        if let Some(dir) = dir {
+            ant.moves.pop();
+            ant.moves.push(move_of(dir));
+        }
+        ant.end_turn = end_turn;
+    }
+
+    /// Replay of all turns recorded so far.
+    pub fn replay(&self) -> Replay {
+        let mut waters: Vec<Position> =
+            self.waters.iter().cloned().collect();
+        waters.sort();
+
+        let mut hills: Vec<ReplayHill> = self
+            .hills
+            .iter()
+            .map(|((pos, owner), &end_turn)| ReplayHill {
+                pos: pos.clone(),
+                owner: *owner,
+                end_turn,
+            })
+            .collect();
+        hills.sort_by(|a, b| {
+            (&a.pos, a.owner).cmp(&(&b.pos, b.owner))
+        });
+
+        let players = self
+            .ants
+            .iter()
+            .map(|a| a.owner)
+            .chain(hills.iter().map(|h| h.owner))
+            .max()
+            .map_or(1, |owner| owner + 1);
+
+        Replay {
+            params: self.params.clone(),
+            players,
+            waters,
+            ants: self.ants.clone(),
+            foods: self.foods.clone(),
+            hills,
+        }
+    }
+
+    /// Write replay of all turns recorded so far to file.
+    pub fn write_file(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, write_replay(&self.replay())).map_err(
+            |e| format!("Can not write replay '{}': {}", path, e),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FooAgent;
+
+    /// Player 0 ant walks east, gathering food converted into a new
+    /// ant on its hill. Player 1 ant is killed on turn 3.
+    const REPLAY: &str = r#"{
+        "challenge": "ants",
+        "replayformat": "json",
+        "replaydata": {
+            "revision": 3,
+            "players": 2,
+            "turns": 10,
+            "turntime": 500,
+            "loadtime": 3000,
+            "viewradius2": 25,
+            "attackradius2": 5,
+            "spawnradius2": 1,
+            "map": {
+                "rows": 3,
+                "cols": 20,
+                "data": [
+                    "....%...............",
+                    "....................",
+                    "...................."
+                ]
+            },
+            "ants": [
+                [1, 1, 0, 0, 10, 0, "eeesw"],
+                [1, 6, 1, 3, 10, 0, "---"],
+                [1, 9, 0, 0, 3, 1, "ww-"]
+            ],
+            "food": [[2, 11, 0, 10]],
+            "hills": [[1, 1, 0, 10], [1, 10, 1, 6]]
+        }
+    }"#;
+
+    #[test]
+    fn parse_replay_data() {
+        let replay = parse_replay(REPLAY).expect("valid replay");
+
+        assert_eq!(replay.size(), pos(3, 20));
+        assert_eq!(replay.players, 2);
+        assert_eq!(replay.params.viewradius2, 25);
+        assert_eq!(replay.waters, vec![pos(0, 4)]);
+        assert_eq!(replay.ants.len(), 3);
+        assert_eq!(replay.ants[0].moves, "eeesw");
+        assert_eq!(replay.hills[1].owner, 1);
+    }
+
+    #[test]
+    fn world_state_of_player() {
+        let replay = parse_replay(REPLAY).expect("valid replay");
+
+        let world = replay.world_state(2, 0);
+
+        assert_eq!(world.live_ants_for_player(0), vec![pos(1, 3)]);
+        assert_eq!(world.live_ants_for_player(1), vec![pos(1, 7)]);
+        assert_eq!(world.foods, vec![pos(1, 6)]);
+        assert_eq!(world.hills, vec![vec![pos(1, 1)]]);
+        assert_eq!(world.waters, vec![pos(0, 4)]);
+    }
+
+    #[test]
+    fn world_state_relative_to_other_player() {
+        let replay = parse_replay(REPLAY).expect("valid replay");
+
+        let world = replay.world_state(3, 1);
+
+        // Player 1 ant is dead and nothing is visible anymore.
+        assert_eq!(world, WorldState::default());
+
+        let world = replay.world_state(2, 1);
+        assert_eq!(world.live_ants_for_player(0), vec![pos(1, 7)]);
+        assert_eq!(world.live_ants_for_player(1), vec![pos(1, 3)]);
+        assert_eq!(world.hills, vec![vec![pos(1, 10)]]);
+    }
+
+    /// Player 0 sees the ant of player 2 from the start and the ant
+    /// of player 1 from turn 7 on.
+    const THREE_PLAYER_REPLAY: &str = r#"{
+        "players": 3,
+        "turns": 10,
+        "viewradius2": 25,
+        "map": {
+            "rows": 3,
+            "cols": 20,
+            "data": [
+                "....................",
+                "....................",
+                "...................."
+            ]
+        },
+        "ants": [
+            [1, 1, 0, 0, 10, 0, "----------"],
+            [2, 12, 0, 0, 10, 1, "wwwwwwwww"],
+            [0, 3, 0, 0, 10, 2, "----------"]
+        ],
+        "food": [],
+        "hills": []
+    }"#;
+
+    #[test]
+    fn number_enemies_in_order_first_seen() {
+        let replay =
+            parse_replay(THREE_PLAYER_REPLAY).expect("valid replay");
+
+        let world = replay.world_state(6, 0);
+        assert_eq!(world.live_ants_for_player(1), vec![pos(0, 3)]);
+        assert_eq!(world.live_ants_for_player(2), vec![]);
+
+        let world = replay.world_state(7, 0);
+        assert_eq!(world.live_ants_for_player(1), vec![pos(0, 3)]);
+        assert_eq!(world.live_ants_for_player(2), vec![pos(2, 5)]);
+
+        // Player 1 sees player 2 before player 0.
+        let world = replay.world_state(7, 1);
+        assert_eq!(world.live_ants_for_player(1), vec![pos(0, 3)]);
+        assert_eq!(world.live_ants_for_player(2), vec![pos(1, 1)]);
+    }
+
+    #[test]
+    fn parse_written_replay() {
+        let replay = parse_replay(REPLAY).expect("valid replay");
+
+        let written = write_replay(&replay);
+
+        assert_eq!(parse_replay(&written), Ok(replay));
+    }
+
+    #[test]
+    fn record_turns() {
+        let replay = parse_replay(REPLAY).expect("valid replay");
+        let mut recorder = ReplayRecorder::new(&replay.params);
+
+        recorder.record_turn(
+            1,
+            &replay.world_state(1, 0),
+            &[pos(1, 2).east()],
+        );
+        recorder.record_turn(2, &replay.world_state(2, 0), &[]);
+        let recorded = recorder.replay();
+
+        assert_eq!(recorded.players, 2);
+        assert_eq!(recorded.ants.len(), 2, "ants followed");
+        assert_eq!(recorded.ants[0].moves, "e-");
+        assert_eq!(
+            recorded.world_state(1, 0),
+            replay.world_state(1, 0)
+        );
+        assert_eq!(
+            recorded.world_state(2, 0),
+            replay.world_state(2, 0)
+        );
+    }
+
+    #[test]
+    fn replay_turn_with_agent() {
+        let replay = parse_replay(REPLAY).expect("valid replay");
+        let mut agent = FooAgent::default().quiet();
+        agent.prepare(replay.params.clone());
+
+        let orders = agent.make_turn(replay.world_state(4, 0), 4);
+
+        assert_eq!(orders.len(), 2);
+    }
+}
@@ -0,0 +1,680 @@
+use crate::replay::Replay;
+use crate::replay::ReplayRecorder;
+use crate::utilities::*;
+use crate::world_step::distance2;
+use crate::world_step::positions_within;
+use ants_ai_challenge_api::*;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+/// Chance for each player, each turn, that a new food is spawned at
+/// a random free land position.
+const FOOD_SPAWN_CHANCE_PERCENT: u32 = 30;
+
+/// Attempts at finding a free land position for spawning food.
+const FOOD_SPAWN_ATTEMPTS: usize = 100;
+
+/// Points for razing an enemy hill.
+const RAZE_POINTS: i32 = 2;
+
+/// Points lost when own hill is razed.
+const RAZED_POINTS: i32 = -1;
+
+/// Start state of a game, i.e. water, hills, food and possibly ants.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Map {
+    pub world: WorldState,
+    pub size: Position,
+    pub players: usize,
+}
+
+impl Map {
+    /// Map with as many players as there are hill or ant owners.
+    pub fn new(world: WorldState, size: Position) -> Map {
+        let players =
+            std::cmp::max(world.hills.len(), world.live_ants.len());
+        Map {
+            world,
+            size,
+            players,
+        }
+    }
+
+    /// Map from ASCII-art format, see `utilities::world`.
+    pub fn from_line_map(map: &'static str) -> Map {
+        Map::new(world(map), size_of_world(map))
+    }
+}
+
+/// Outcome of a game, for each player.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameResult {
+    pub turns: u32,
+    pub scores: Vec<i32>,
+    pub ants_alive: Vec<usize>,
+    pub hills_razed: Vec<u32>,
+}
+
+impl GameResult {
+    /// Players with the highest score.
+    pub fn winners(&self) -> Vec<usize> {
+        let best = self.scores.iter().max().cloned();
+        (0..self.scores.len())
+            .filter(|&p| Some(self.scores[p]) == best)
+            .collect()
+    }
+}
+
+/// Local game engine implementing the Ants rules, driving any
+/// agents. Each turn, every player gets its own fog of war view of
+/// the world and the given orders are then resolved in the order:
+/// movement, collisions, battle, razing of hills, gathering of food,
+/// spawning of ants and finally spawning of new food.
+///
+/// Randomness comes from the given seed only, so given the same
+/// agents and seed, a game is always played the same way.
+pub struct Game {
+    params: GameParameters,
+    size: Position,
+    players: usize,
+    waters: HashSet<Position>,
+    foods: HashSet<Position>,
+    hills: HashMap<Position, usize>,
+    ants: HashMap<Position, usize>,
+    dead_ants: Vec<(Position, usize)>,
+    stored_food: Vec<u32>,
+    scores: Vec<i32>,
+    hills_razed: Vec<u32>,
+    turn: u32,
+    rng: StdRng,
+    recorder: Option<ReplayRecorder>,
+}
+
+impl Game {
+    /// New game on given map, using the radiuses, number of turns
+    /// and time limits of given parameters. Players without ants on
+    /// the map start with one ant on each of their hills.
+    pub fn new(
+        map: &Map,
+        params: &GameParameters,
+        seed: u64,
+    ) -> Game {
+        let mut hills = HashMap::new();
+        let mut ants = HashMap::new();
+
+        for player in 0..map.players {
+            let player_hills = map
+                .world
+                .hills
+                .get(player)
+                .cloned()
+                .unwrap_or_default();
+            let player_ants =
+                map.world.live_ants_for_player(player as u8);
+
+            for hill in player_hills.iter() {
+                hills.insert(hill.clone(), player);
+            }
+
+            let start_ants = if player_ants.is_empty() {
+                player_hills
+            } else {
+                player_ants
+            };
+            for ant in start_ants {
+                ants.insert(ant, player);
+            }
+        }
+
+        Game {
+            params: GameParameters {
+                rows: map.size.row.into(),
+                cols: map.size.col.into(),
+                ..params.clone()
+            },
+            size: map.size.clone(),
+            players: map.players,
+            waters: map.world.waters.iter().cloned().collect(),
+            foods: map.world.foods.iter().cloned().collect(),
+            hills,
+            ants,
+            dead_ants: vec![],
+            stored_food: vec![0; map.players],
+            scores: (0..map.players)
+                .map(|p| hill_count(&map.world, p) as i32)
+                .collect(),
+            hills_razed: vec![0; map.players],
+            turn: 0,
+            rng: StdRng::seed_from_u64(seed),
+            recorder: None,
+        }
+    }
+
+    /// Record each turn's world and orders of all players, see
+    /// `replay`.
+    pub fn recording_replay(mut self) -> Game {
+        self.recorder = Some(ReplayRecorder::new(&self.params));
+        self
+    }
+
+    /// Replay of the turns played so far, if recording.
+    pub fn replay(&self) -> Option<Replay> {
+        self.recorder.as_ref().map(|r| r.replay())
+    }
+
+    /// Game parameters, as given to each player.
+    pub fn params(&self) -> GameParameters {
+        self.params.clone()
+    }
+
+    pub fn turn(&self) -> u32 {
+        self.turn
+    }
+
+    /// Player is still in the game while having ants or hills.
+    pub fn is_alive(&self, player: usize) -> bool {
+        self.ants.values().any(|&p| p == player)
+            || self.hills.values().any(|&p| p == player)
+    }
+
+    pub fn is_over(&self) -> bool {
+        let alive = (0..self.players).filter(|&p| self.is_alive(p));
+        self.turn >= self.params.turns as u32 || alive.count() < 2
+    }
+
+    /// Play a full game, where agents are given in player order.
+    pub fn play(
+        &mut self,
+        agents: &mut [Box<dyn Agent>],
+    ) -> GameResult {
+        for agent in agents.iter_mut() {
+            agent.prepare(self.params());
+        }
+
+        while !self.is_over() {
+            self.play_turn(agents);
+        }
+
+        self.result()
+    }
+
+    /// Let each player still alive make a turn and resolve the
+    /// given orders.
+    pub fn play_turn(&mut self, agents: &mut [Box<dyn Agent>]) {
+        self.turn += 1;
+
+        let orders: Vec<Orders> = agents
+            .iter_mut()
+            .enumerate()
+            .map(|(player, agent)| {
+                if self.is_alive(player) {
+                    agent.make_turn(
+                        self.player_view(player),
+                        self.turn,
+                    )
+                } else {
+                    vec![]
+                }
+            })
+            .collect();
+
+        if self.recorder.is_some() {
+            let world = self.full_world();
+            let all_orders: Orders = orders.concat();
+            if let Some(recorder) = self.recorder.as_mut() {
+                recorder.record_turn(self.turn, &world, &all_orders);
+            }
+        }
+
+        self.dead_ants.clear();
+        self.move_ants(&orders);
+        self.battle();
+        self.raze_hills();
+        self.gather_food();
+        self.spawn_ants();
+        self.spawn_food();
+    }
+
+    pub fn result(&self) -> GameResult {
+        GameResult {
+            turns: self.turn,
+            scores: self.scores.clone(),
+            ants_alive: (0..self.players)
+                .map(|p| {
+                    self.ants.values().filter(|&&o| o == p).count()
+                })
+                .collect(),
+            hills_razed: self.hills_razed.clone(),
+        }
+    }
+
+    /// World as seen by given player, through the fog of war. All
+    /// owners are relative to the player, the player itself always
+    /// being player zero.
+    pub fn player_view(&self, player: usize) -> WorldState {
+        let own_ants: Vec<Position> = sorted(
+            self.ants
+                .iter()
+                .filter(|(_, &owner)| owner == player)
+                .map(|(p, _)| p.clone()),
+        );
+        let visible = positions_within(
+            &own_ants,
+            self.params.viewradius2 as u32,
+            &self.size,
+        );
+        self.view(player, |p| visible.contains(p))
+    }
+
+    /// The whole world, without fog of war.
+    pub fn full_world(&self) -> WorldState {
+        self.view(0, |_| true)
+    }
+
+    /// World of positions that are visible, with owners relative to
+    /// given player.
+    fn view(
+        &self,
+        player: usize,
+        visible: impl Fn(&Position) -> bool,
+    ) -> WorldState {
+        let relative = |owner: usize| {
+            ((owner + self.players - player) % self.players) as u8
+        };
+
+        let mut world = WorldState::default();
+
+        for water in sorted(self.waters.iter().cloned()) {
+            if visible(&water) {
+                world = world.water(water);
+            }
+        }
+        for food in sorted(self.foods.iter().cloned()) {
+            if visible(&food) {
+                world = world.food(food);
+            }
+        }
+        for (hill, owner) in sorted_entries(&self.hills) {
+            if visible(&hill) {
+                world = world.hill(hill, relative(owner));
+            }
+        }
+        for (ant, owner) in sorted_entries(&self.ants) {
+            if visible(&ant) {
+                world = world.live_ant(ant, relative(owner));
+            }
+        }
+        for (ant, owner) in self.dead_ants.iter() {
+            if visible(ant) {
+                world = world.dead_ant(ant.clone(), relative(*owner));
+            }
+        }
+        world
+    }
+
+    /// Move ants according to the orders of each player. Orders for
+    /// ants not owned by the player, repeated orders and orders into
+    /// water are ignored. Ants ending up on the same position die.
+    fn move_ants(&mut self, orders: &[Orders]) {
+        let mut moved: HashMap<Position, Vec<usize>> = HashMap::new();
+        let mut ordered = HashSet::new();
+
+        for (player, player_orders) in orders.iter().enumerate() {
+            for order in player_orders {
+                let target = order.target_pos(&self.size);
+                let is_valid = self.ants.get(&order.pos)
+                    == Some(&player)
+                    && !ordered.contains(&order.pos)
+                    && !self.waters.contains(&target);
+
+                if is_valid {
+                    ordered.insert(order.pos.clone());
+                    moved.entry(target).or_default().push(player);
+                }
+            }
+        }
+
+        for (ant, &owner) in self.ants.iter() {
+            if !ordered.contains(ant) {
+                moved.entry(ant.clone()).or_default().push(owner);
+            }
+        }
+
+        self.ants.clear();
+        for (position, owners) in sorted_entries(&moved) {
+            if owners.len() == 1 {
+                self.ants.insert(position, owners[0]);
+            } else {
+                for owner in owners {
+                    self.dead_ants.push((position.clone(), owner));
+                }
+            }
+        }
+    }
+
+    /// Resolve battles according to the focus rule. An ant dies if
+    /// any enemy in attack range is in range of at most as many
+    /// enemies as the ant itself.
+    fn battle(&mut self) {
+        let ants = sorted_entries(&self.ants);
+        let enemies_in_range = |(pos, owner): &(Position, usize)| {
+            ants.iter()
+                .filter(|(p, o)| {
+                    o != owner
+                        && distance2(p, pos, &self.size)
+                            <= self.params.attackradius2 as u32
+                })
+                .cloned()
+                .collect::<Vec<(Position, usize)>>()
+        };
+
+        let dying: Vec<(Position, usize)> = ants
+            .iter()
+            .filter(|ant| {
+                let enemies = enemies_in_range(ant);
+                enemies.iter().any(|enemy| {
+                    enemies_in_range(enemy).len() <= enemies.len()
+                })
+            })
+            .cloned()
+            .collect();
+
+        for (ant, owner) in dying {
+            self.ants.remove(&ant);
+            self.dead_ants.push((ant, owner));
+        }
+    }
+
+    /// Hills with an enemy ant on them are razed.
+    fn raze_hills(&mut self) {
+        for (hill, owner) in sorted_entries(&self.hills) {
+            match self.ants.get(&hill) {
+                Some(&raider) if raider != owner => {
+                    self.hills.remove(&hill);
+                    self.hills_razed[raider] += 1;
+                    self.scores[raider] += RAZE_POINTS;
+                    self.scores[owner] += RAZED_POINTS;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Food within spawn radius of ants of a single player is
+    /// gathered by that player. Food contested by several players is
+    /// destroyed.
+    fn gather_food(&mut self) {
+        for food in sorted(self.foods.iter().cloned()) {
+            let gatherers: HashSet<usize> = self
+                .ants
+                .iter()
+                .filter(|(ant, _)| {
+                    distance2(ant, &food, &self.size)
+                        <= self.params.spawnradius2 as u32
+                })
+                .map(|(_, &owner)| owner)
+                .collect();
+
+            if !gatherers.is_empty() {
+                self.foods.remove(&food);
+            }
+            if gatherers.len() == 1 {
+                for owner in gatherers {
+                    self.stored_food[owner] += 1;
+                }
+            }
+        }
+    }
+
+    /// Each free hill spawns an ant, if its owner has stored food.
+    fn spawn_ants(&mut self) {
+        for (hill, owner) in sorted_entries(&self.hills) {
+            if self.stored_food[owner] > 0
+                && !self.ants.contains_key(&hill)
+            {
+                self.stored_food[owner] -= 1;
+                self.ants.insert(hill, owner);
+            }
+        }
+    }
+
+    /// Randomly spawn food at free land positions.
+    fn spawn_food(&mut self) {
+        let rows = self.size.row;
+        let cols = self.size.col;
+        if rows == 0 || cols == 0 {
+            return;
+        }
+
+        for _ in 0..self.players {
+            if self.rng.gen_range(0, 100) >= FOOD_SPAWN_CHANCE_PERCENT
+            {
+                continue;
+            }
+
+            for _ in 0..FOOD_SPAWN_ATTEMPTS {
+                let p = pos(
+                    self.rng.gen_range(0, rows),
+                    self.rng.gen_range(0, cols),
+                );
+                let is_free = !self.waters.contains(&p)
+                    && !self.foods.contains(&p)
+                    && !self.hills.contains_key(&p)
+                    && !self.ants.contains_key(&p);
+
+                if is_free {
+                    self.foods.insert(p);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn hill_count(world: &WorldState, player: usize) -> usize {
+    world.hills.get(player).map_or(0, |hills| hills.len())
+}
+
+fn sorted<I: Iterator<Item = Position>>(
+    positions: I,
+) -> Vec<Position> {
+    let mut result: Vec<Position> = positions.collect();
+    result.sort();
+    result
+}
+
+fn sorted_entries<T: Clone>(
+    map: &HashMap<Position, T>,
+) -> Vec<(Position, T)> {
+    let mut result: Vec<(Position, T)> =
+        map.iter().map(|(p, t)| (p.clone(), t.clone())).collect();
+    result.sort_by(|a, b| a.0.cmp(&b.0));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Agent ordering all its ants in the same direction.
+    struct Walker {
+        dir: Direction,
+    }
+
+    impl Agent for Walker {
+        fn prepare(&mut self, _params: GameParameters) {}
+
+        fn make_turn(
+            &mut self,
+            world: WorldState,
+            _turn: u32,
+        ) -> Orders {
+            world
+                .live_ants_for_player(0)
+                .iter()
+                .map(|ant| ant.order(self.dir))
+                .collect()
+        }
+    }
+
+    /// Agent never moving any ants.
+    struct Idle {}
+
+    impl Agent for Idle {
+        fn prepare(&mut self, _params: GameParameters) {}
+
+        fn make_turn(
+            &mut self,
+            _world: WorldState,
+            _turn: u32,
+        ) -> Orders {
+            vec![]
+        }
+    }
+
+    fn params(
+        attackradius2: u32,
+        spawnradius2: u32,
+    ) -> GameParameters {
+        GameParameters {
+            attackradius2,
+            spawnradius2,
+            viewradius2: 9,
+            turns: 20,
+            ..GameParameters::default()
+        }
+    }
+
+    fn walker(dir: Direction) -> Box<dyn Agent> {
+        Box::new(Walker { dir })
+    }
+
+    fn ants(game: &Game) -> Vec<(Position, usize)> {
+        sorted_entries(&game.ants)
+    }
+
+    #[test]
+    fn ants_colliding_die() {
+        let map = &Map::from_line_map("a-b---%%");
+        let mut game = Game::new(map, &params(0, 0), 0);
+
+        game.play_turn(&mut [walker(East), walker(West)]);
+
+        assert_eq!(ants(&game), vec![]);
+        assert_eq!(
+            game.dead_ants,
+            vec![(pos(0, 1), 0), (pos(0, 1), 1)]
+        );
+        assert!(game.is_over());
+    }
+
+    #[test]
+    fn moving_into_water_is_ignored() {
+        let map = &Map::from_line_map("a%--b---");
+        let mut game = Game::new(map, &params(0, 0), 0);
+
+        game.play_turn(&mut [walker(East), walker(East)]);
+
+        assert_eq!(ants(&game), vec![(pos(0, 0), 0), (pos(0, 5), 1)]);
+    }
+
+    #[test]
+    fn battle_by_focus_rule() {
+        let map = &Map::from_line_map("-a-b-a--------");
+        let mut game = Game::new(map, &params(4, 0), 0);
+
+        game.play_turn(&mut [Box::new(Idle {}), Box::new(Idle {})]);
+
+        assert_eq!(ants(&game), vec![(pos(0, 1), 0), (pos(0, 5), 0)]);
+        assert_eq!(game.dead_ants, vec![(pos(0, 3), 1)]);
+    }
+
+    #[test]
+    fn gather_food_and_spawn_ant_on_hill() {
+        let map = &Map::from_line_map("A-*-----%%%%");
+        let mut game = Game::new(map, &params(0, 1), 0);
+
+        game.play_turn(&mut [walker(East)]);
+
+        assert_eq!(ants(&game), vec![(pos(0, 0), 0), (pos(0, 1), 0)]);
+        assert!(!game.foods.contains(&pos(0, 2)));
+    }
+
+    #[test]
+    fn raze_enemy_hill() {
+        let map = &Map::from_line_map("-a1------b");
+        let mut game = Game::new(map, &params(1, 0), 0);
+
+        game.play_turn(&mut [walker(East), Box::new(Idle {})]);
+
+        let result = game.result();
+        assert_eq!(result.hills_razed, vec![1, 0]);
+        assert_eq!(result.scores, vec![2, 0]);
+        assert_eq!(result.winners(), vec![0]);
+    }
+
+    #[test]
+    fn view_through_fog_of_war_relative_to_player() {
+        let map = &Map::from_line_map("a--b----*--");
+        let game = Game::new(map, &params(0, 0), 0);
+
+        let view = game.player_view(1);
+
+        assert_eq!(view.live_ants_for_player(0), vec![pos(0, 3)]);
+        assert_eq!(view.live_ants_for_player(1), vec![pos(0, 0)]);
+        assert_eq!(view.foods, vec![]);
+    }
+
+    #[test]
+    fn same_seed_gives_same_game() {
+        let map = &Map::from_line_map(
+            "0-------
+             --------
+             --%%----
+             --------
+             -------1",
+        );
+        let play = || {
+            Game::new(map, &params(5, 1), 42)
+                .play(&mut [walker(South), walker(West)])
+        };
+
+        let result = play();
+
+        assert_eq!(result.turns, 20);
+        assert_eq!(result, play());
+    }
+
+    #[test]
+    fn record_replay_of_all_turns() {
+        let map = &Map::from_line_map(
+            "0-------
+             --------
+             --%%----
+             --------
+             -------1",
+        );
+        let mut game =
+            Game::new(map, &params(5, 1), 7).recording_replay();
+        let mut agents = [walker(South), walker(West)];
+        let mut views = vec![];
+
+        while !game.is_over() {
+            views.push((game.turn() + 1, game.player_view(1)));
+            game.play_turn(&mut agents);
+        }
+        let replay = game.replay().expect("recording");
+
+        for (turn, view) in views {
+            assert_eq!(
+                replay.world_state(turn, 1),
+                view,
+                "turn {}",
+                turn
+            );
+        }
+    }
+}
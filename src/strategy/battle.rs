@@ -0,0 +1,334 @@
+use crate::strategy::*;
+use crate::world_step::*;
+
+use std::collections::HashSet;
+
+/// Ant-on-ant battle strategy, based on the "focus" battle rule of
+/// the game: An ant dies if it is in attack range of an enemy ant
+/// that has as many or fewer enemies in range than itself.
+///
+/// Only ants that can get in attack range of an enemy ant in this
+/// turn are considered. Such an ant attacks if it survives the
+/// encounter, otherwise it holds or retreats to a position out of
+/// reach of the enemy.
+pub struct Battle {
+    attack_radius2: u32,
+}
+
+/// Predicted outcome of moving an ant to a specific position.
+struct Outcome {
+    enemies_in_reach: usize,
+    weakest_enemy_support: usize,
+    distance_to_enemies: u32,
+}
+
+impl Outcome {
+    fn survives(&self) -> bool {
+        self.weakest_enemy_support > self.enemies_in_reach
+    }
+}
+
+/// The position itself and all positions one step away.
+fn moves(p: &Position, size: &Position) -> Vec<Position> {
+    vec![
+        p.clone(),
+        p.north().target_pos(size),
+        p.south().target_pos(size),
+        p.east().target_pos(size),
+        p.west().target_pos(size),
+    ]
+}
+
+impl Battle {
+    pub fn new(attack_radius2: u32) -> Battle {
+        Battle { attack_radius2 }
+    }
+
+    /// Squared radius within which an enemy ant can possibly be in
+    /// attack range after both ants have moved one step.
+    fn engagement_radius2(&self) -> u32 {
+        let radius =
+            f64::from(self.attack_radius2).sqrt().ceil() as u32;
+        (radius + 2) * (radius + 2)
+    }
+
+    fn in_reach(
+        &self,
+        a_moves: &[Position],
+        b_moves: &[Position],
+        size: &Position,
+    ) -> bool {
+        a_moves.iter().any(|a| {
+            b_moves
+                .iter()
+                .any(|b| distance2(a, b, size) <= self.attack_radius2)
+        })
+    }
+
+    fn outcome(
+        &self,
+        ant: &Position,
+        target: &Position,
+        enemies: &[Position],
+        friends: &[Position],
+        size: &Position,
+    ) -> Outcome {
+        let target_moves = vec![target.clone()];
+
+        let enemies_in_reach: Vec<&Position> = enemies
+            .iter()
+            .filter(|e| {
+                self.in_reach(&target_moves, &moves(e, size), size)
+            })
+            .collect();
+
+        let weakest_enemy_support = enemies_in_reach
+            .iter()
+            .map(|e| {
+                let enemy_moves = moves(e, size);
+                let other_friends = friends
+                    .iter()
+                    .filter(|&f| f != ant)
+                    .filter(|f| {
+                        self.in_reach(
+                            &moves(f, size),
+                            &enemy_moves,
+                            size,
+                        )
+                    })
+                    .count();
+                other_friends + 1
+            })
+            .min()
+            .unwrap_or(usize::MAX);
+
+        let distance_to_enemies = enemies_in_reach
+            .iter()
+            .map(|e| distance2(target, e, size))
+            .sum();
+
+        Outcome {
+            enemies_in_reach: enemies_in_reach.len(),
+            weakest_enemy_support,
+            distance_to_enemies,
+        }
+    }
+
+    /// Choose between attacking, holding and retreating. None if no
+    /// enemy can be in reach this turn, whatever the ant does, and
+    /// otherwise the direction to go, where None means holding the
+    /// current position.
+    fn choose_direction(
+        &self,
+        ant: &Position,
+        world_step: &dyn WorldStep,
+        enemies: &[Position],
+        friends: &[Position],
+    ) -> Option<Option<Direction>> {
+        let size = world_step.size();
+
+        let mut candidates: Vec<(Option<Direction>, Outcome)> =
+            vec![(
+                None,
+                self.outcome(ant, ant, enemies, friends, size),
+            )];
+
+        for dir in world_step.available_directions(ant) {
+            let target = ant.order(dir).target_pos(size);
+            candidates.push((
+                Some(dir),
+                self.outcome(ant, &target, enemies, friends, size),
+            ));
+        }
+
+        if candidates.iter().all(|(_, o)| o.enemies_in_reach == 0) {
+            return None;
+        }
+
+        let attack = candidates
+            .iter()
+            .filter(|(_, o)| o.survives() && o.enemies_in_reach > 0)
+            .min_by_key(|(_, o)| {
+                (
+                    std::cmp::Reverse(o.enemies_in_reach),
+                    o.distance_to_enemies,
+                )
+            });
+
+        let retreat =
+            candidates.iter().find(|(_, o)| o.enemies_in_reach == 0);
+
+        // Holding first among equally bad moves.
+        let least_bad = candidates.iter().min_by_key(|(_, o)| {
+            std::cmp::Reverse(
+                o.weakest_enemy_support as i64
+                    - o.enemies_in_reach as i64,
+            )
+        });
+
+        attack.or(retreat).or(least_bad).map(|(dir, _)| *dir)
+    }
+}
+
+impl Strategy for Battle {
+    fn apply(
+        &mut self,
+        world_step: &dyn WorldStep,
+        ants_available: &mut HashSet<Position>,
+        _deadline: &Deadline,
+    ) -> Orders {
+        let size = world_step.size();
+        let engagement_radius2 = self.engagement_radius2();
+        let enemies = world_step.get_enemy_positions(TileKind::Ant);
+        let friends = world_step.all_my_ants();
+
+        let mut ants: Vec<Position> =
+            ants_available.iter().cloned().collect();
+        ants.sort();
+
+        let mut orders = vec![];
+
+        for ant in ants {
+            let enemies_near: Vec<Position> = enemies
+                .iter()
+                .filter(|e| {
+                    distance2(&ant, e, size) <= engagement_radius2
+                })
+                .cloned()
+                .collect();
+
+            if enemies_near.is_empty() {
+                continue;
+            }
+
+            // Ants out of reach of the enemies are left to the other
+            // strategies.
+            if let Some(dir) = self.choose_direction(
+                &ant,
+                world_step,
+                &enemies_near,
+                &friends,
+            ) {
+                ants_available.remove(&ant);
+                if let Some(dir) = dir {
+                    orders.push(ant.order(dir));
+                }
+            }
+        }
+        orders
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply_battle(
+        map: &'static str,
+        attack_radius2: u32,
+    ) -> (Orders, HashSet<Position>) {
+        let world_step = &BasicWorldStep::new_from_line_map(map);
+        let strategy = &mut Battle::new(attack_radius2);
+
+        let mut ants: HashSet<Position> =
+            world_step.all_my_ants().iter().cloned().collect();
+
+        let orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
+        (orders, ants)
+    }
+
+    #[test]
+    fn ignore_ants_far_from_enemies() {
+        let (orders, ants) = apply_battle(
+            "a-----b-------
+             --------------
+             --------------",
+            5,
+        );
+
+        assert_eq![orders, vec![]];
+        assert_eq![ants, set![pos(0, 0)]];
+    }
+
+    #[test]
+    fn retreat_from_even_fight() {
+        let (orders, ants) = apply_battle(
+            "---------
+             ---------
+             --a--b---
+             ---------
+             ---------",
+            5,
+        );
+
+        assert_eq![orders, vec![pos(2, 2).west()]];
+        assert_eq![ants, set![]];
+    }
+
+    #[test]
+    fn attack_when_outnumbering_enemy() {
+        let (orders, ants) = apply_battle(
+            "---------
+             ---------
+             --a--b---
+             --a------
+             ---------",
+            5,
+        );
+
+        assert_eq![orders, vec![pos(2, 2).east(), pos(3, 2).east()]];
+        assert_eq![ants, set![]];
+    }
+
+    #[test]
+    fn leave_ants_out_of_reach_to_other_strategies() {
+        // Within engagement radius, but not in reach after one step.
+        let (orders, ants) = apply_battle(
+            "------------
+             ------------
+             a----b------
+             ------------
+             ------------",
+            5,
+        );
+
+        assert_eq![orders, vec![]];
+        assert_eq![ants, set![pos(2, 0)]];
+    }
+
+    #[test]
+    fn stay_out_of_reach_of_enemy() {
+        let world_step = &AvoidWaterFilter::new_from_line_map(
+            "%%%%%%
+             %a--b-
+             %%%%%%",
+        );
+        let strategy = &mut Battle::new(1);
+
+        let mut ants = set![pos(1, 1)];
+        let orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
+
+        assert_eq![orders, vec![]];
+        assert_eq![ants, set![]];
+    }
+
+    #[test]
+    fn hold_when_retreat_is_blocked() {
+        // The enemy can reach the ant wherever it goes.
+        let world_step = &AvoidWaterFilter::new_from_line_map(
+            "%%%%%%
+             %a-b--
+             %%%%%%",
+        );
+        let strategy = &mut Battle::new(1);
+
+        let mut ants = set![pos(1, 1)];
+        let orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
+
+        assert_eq![orders, vec![]];
+        assert_eq![ants, set![]];
+    }
+}
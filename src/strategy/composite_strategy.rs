@@ -2,41 +2,101 @@ use crate::strategy::*;
 use crate::world_step::WorldStep;
 
 use std::collections::HashSet;
+use std::time::Duration;
 
+/// Time left of the turn when remaining strategies are skipped in
+/// favour of the fast fallback strategy.
+const FALLBACK_MARGIN: Duration = Duration::from_millis(10);
+
+/// Applies strategies in order, where each strategy can only give
+/// orders to ants not already given orders by previous strategies.
+/// When the deadline is near, remaining ants fall back to a random
+/// walk.
 pub struct CompositeStrategy {
-    strategies: Vec<&'static dyn Strategy>,
+    strategies: Vec<Box<dyn Strategy>>,
 }
 
 impl CompositeStrategy {
-    pub fn new_with_default() -> CompositeStrategy {
-        CompositeStrategy {
-            strategies: vec![
-                &HillRaiser {},
-                &GatherFood {},
-                &SpreadOutScout {},
-                &RandomWalk {},
-            ],
+    /// Strategies applied in given order.
+    pub fn new(
+        strategies: Vec<Box<dyn Strategy>>,
+    ) -> CompositeStrategy {
+        CompositeStrategy { strategies }
+    }
+
+    /// Like apply_tracked, but reporting the orders of each strategy
+    /// as soon as they are ready, e.g. for planning on a worker
+    /// thread.
+    pub fn apply_reporting(
+        &mut self,
+        world_step: &dyn WorldStep,
+        ants_available: &mut HashSet<Position>,
+        tracker: &mut AntTracker,
+        deadline: &Deadline,
+        report: &mut dyn FnMut(&[Order]),
+    ) {
+        for strategy in self.strategies.iter_mut() {
+            if deadline.is_near(FALLBACK_MARGIN) {
+                let mut fallback = RandomWalk {};
+                report(&fallback.apply(
+                    world_step,
+                    ants_available,
+                    deadline,
+                ));
+                break;
+            }
+
+            report(&strategy.apply_tracked(
+                world_step,
+                ants_available,
+                tracker,
+                deadline,
+            ));
         }
     }
 }
 
 impl Strategy for CompositeStrategy {
     fn apply(
-        &self,
+        &mut self,
         world_step: &dyn WorldStep,
         ants_available: &mut HashSet<Position>,
+        deadline: &Deadline,
     ) -> Orders {
-        let mut result_orders: Vec<Order> = Vec::new();
+        self.apply_tracked(
+            world_step,
+            ants_available,
+            &mut AntTracker::default(),
+            deadline,
+        )
+    }
 
-        for strategy in self.strategies.iter() {
-            let orders = strategy.apply(world_step, ants_available);
+    fn apply_tracked(
+        &mut self,
+        world_step: &dyn WorldStep,
+        ants_available: &mut HashSet<Position>,
+        tracker: &mut AntTracker,
+        deadline: &Deadline,
+    ) -> Orders {
+        let mut result_orders: Vec<Order> = Vec::new();
 
-            for o in orders {
-                result_orders.push(o);
-            }
-        }
+        self.apply_reporting(
+            world_step,
+            ants_available,
+            tracker,
+            deadline,
+            &mut |orders| result_orders.extend_from_slice(orders),
+        );
         result_orders
     }
+
+    fn cache_stats(&self) -> CacheStats {
+        self.strategies
+            .iter()
+            .fold(CacheStats::default(), |s, strategy| {
+                s + strategy.cache_stats()
+            })
+    }
 }
 
 #[cfg(test)]
@@ -51,14 +111,17 @@ mod tests {
              -*-a--a-*-
              ----------",
         );
-        let strategy = &CompositeStrategy::new_with_default();
+        let strategy = &mut default_strategy_config()
+            .create(&GameParameters::default())
+            .expect("known strategies");
 
         let left_ant = pos(1, 3);
         let right_ant = pos(1, 6);
         let mut ants: HashSet<Position> =
             set![left_ant.clone(), right_ant.clone()];
 
-        let actual_orders = strategy.apply(world_step, &mut ants);
+        let actual_orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
 
         assert_eq![ants, set![]];
 
@@ -67,4 +130,27 @@ mod tests {
             vec![left_ant.west(), right_ant.east()]
         ];
     }
+
+    #[test]
+    fn fall_back_to_random_walk_when_deadline_is_passed() {
+        let world_step = &mut AvoidWaterFilter::new_from_line_map(
+            "%%%%%
+             %*a-%
+             %%%%%",
+        );
+        let strategy = &mut default_strategy_config()
+            .create(&GameParameters::default())
+            .expect("known strategies");
+
+        let ant = pos(1, 2);
+        let mut ants: HashSet<Position> = set![ant.clone()];
+
+        let passed = Deadline::after(Duration::from_millis(0));
+        let actual_orders =
+            strategy.apply(world_step, &mut ants, &passed);
+
+        // Ant is not sent to food, but walks in random direction
+        assert_eq![ants, set![ant]];
+        assert_eq![actual_orders.len(), 1];
+    }
 }
@@ -0,0 +1,393 @@
+use crate::strategy::*;
+use crate::world_step::StepCosts;
+
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+/// Parameter name and its allowed values.
+type Param = (&'static str, RangeInclusive<usize>);
+
+/// Any positive number.
+const POSITIVE: RangeInclusive<usize> = 1..=usize::MAX;
+
+/// Strategies and their parameters, see `parse_strategy_config`.
+const STRATEGY_PARAMS: &[(&str, &[Param])] = &[
+    ("battle", &[]),
+    (
+        "hill_defender",
+        &[("defend_radius2", 1..=u32::MAX as usize)],
+    ),
+    (
+        "hill_raiser",
+        &[
+            ("raiders_per_hill", POSITIVE),
+            ("max_result_len", POSITIVE),
+            ("cutoff_len", POSITIVE),
+        ],
+    ),
+    (
+        "gather_food",
+        &[("max_result_len", POSITIVE), ("cutoff_len", POSITIVE)],
+    ),
+    (
+        "spread_out_scout",
+        &[
+            ("grid_step", 1..=u16::MAX as usize),
+            ("max_result_len", POSITIVE),
+            ("cutoff_len", POSITIVE),
+        ],
+    ),
+    ("random_walk", &[]),
+];
+
+/// Name of the config line setting the step costs instead of adding
+/// a strategy, and its parameters. A cost of 0 turns it off.
+const STEP_COSTS: (&str, &[Param]) = (
+    "step_costs",
+    &[
+        ("danger_cost", 0..=usize::MAX),
+        ("crowd_cost", 0..=usize::MAX),
+    ],
+);
+
+/// Config of the default strategy stack, see
+/// `default_strategy_config`.
+pub const DEFAULT_STRATEGY_CONFIG: &str =
+    include_str!("../../strategies.conf");
+
+/// Strategy with the parameters given in its config line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrategyEntry {
+    pub name: String,
+    pub params: HashMap<String, usize>,
+    /// Number of the config line, for error messages.
+    pub line: usize,
+}
+
+impl StrategyEntry {
+    fn param(&self, key: &str, default: usize) -> usize {
+        self.params.get(key).cloned().unwrap_or(default)
+    }
+
+    /// Strategy of the entry, or an error naming the line of an
+    /// unknown strategy.
+    fn create(
+        &self,
+        params: &GameParameters,
+    ) -> Result<Box<dyn Strategy>, String> {
+        Ok(match self.name.as_str() {
+            "battle" => {
+                Box::new(Battle::new(params.attackradius2 as u32))
+            }
+            "hill_defender" => {
+                let d = HillDefender::default();
+                Box::new(HillDefender::new(self.param(
+                    "defend_radius2",
+                    d.defend_radius2() as usize,
+                ) as u32))
+            }
+            "hill_raiser" => {
+                let d = HillRaiser::default();
+                Box::new(HillRaiser::new(
+                    self.param(
+                        "raiders_per_hill",
+                        d.raiders_per_hill(),
+                    ),
+                    self.param("max_result_len", d.max_result_len()),
+                    self.param("cutoff_len", d.cutoff_len()),
+                ))
+            }
+            "gather_food" => {
+                let d = GatherFood::default();
+                Box::new(GatherFood::new(
+                    self.param("max_result_len", d.max_result_len()),
+                    self.param("cutoff_len", d.cutoff_len()),
+                ))
+            }
+            "spread_out_scout" => {
+                let d = SpreadOutScout::default();
+                Box::new(SpreadOutScout::new(
+                    self.param("grid_step", d.grid_step() as usize)
+                        as u16,
+                    self.param("max_result_len", d.max_result_len()),
+                    self.param("cutoff_len", d.cutoff_len()),
+                ))
+            }
+            "random_walk" => Box::new(RandomWalk {}),
+            unknown => {
+                return Err(format!(
+                    "Line {}: unknown strategy '{}'",
+                    self.line, unknown
+                ))
+            }
+        })
+    }
+}
+
+/// Ordered strategy stack, used for tuning strategies without
+/// recompiling.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrategyConfig {
+    pub entries: Vec<StrategyEntry>,
+    /// Extra step costs of the world step the strategies plan in.
+    pub step_costs: StepCosts,
+}
+
+impl StrategyConfig {
+    /// Composite of the configured strategies, in config order, or
+    /// an error naming the first unknown strategy.
+    pub fn create(
+        &self,
+        params: &GameParameters,
+    ) -> Result<CompositeStrategy, String> {
+        Ok(CompositeStrategy::new(
+            self.entries
+                .iter()
+                .map(|e| e.create(params))
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+}
+
+/// Parse strategy config with one strategy per line, applied in
+/// order, followed by its parameters, e.g.
+///
+/// ```text
+/// # Comment
+/// battle
+/// gather_food max_result_len=3 cutoff_len=15
+/// random_walk
+/// ```
+///
+/// Parameters not given keep their default values. The line
+/// `step_costs`, e.g. `step_costs danger_cost=4 crowd_cost=1`, sets
+/// the extra step costs, see `StepCosts`, instead of adding a
+/// strategy.
+pub fn parse_strategy_config(
+    text: &str,
+) -> Result<StrategyConfig, String> {
+    let mut entries = vec![];
+    let mut step_costs = StepCosts::default();
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut words = line.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => continue,
+        };
+        let keys = STRATEGY_PARAMS
+            .iter()
+            .chain(std::iter::once(&STEP_COSTS))
+            .find(|(known, _)| *known == name)
+            .map(|(_, keys)| keys)
+            .ok_or_else(|| {
+                format!(
+                    "Line {}: unknown strategy '{}'",
+                    index + 1,
+                    name
+                )
+            })?;
+
+        let mut params = HashMap::new();
+        for word in words {
+            let mut parts = word.splitn(2, '=');
+            let key = parts.next().unwrap_or("");
+            let value = parts.next().unwrap_or("");
+
+            let range = keys
+                .iter()
+                .find(|(known, _)| *known == key)
+                .map(|(_, range)| range)
+                .ok_or_else(|| {
+                    format!(
+                        "Line {}: unknown parameter '{}' of {}",
+                        index + 1,
+                        key,
+                        name
+                    )
+                })?;
+            let number = match value.parse::<usize>() {
+                Ok(number) if range.contains(&number) => number,
+                _ => return Err(format!(
+                    "Line {}: bad number '{}' for {}, not in {:?}",
+                    index + 1,
+                    value,
+                    key,
+                    range
+                )),
+            };
+            params.insert(key.to_string(), number);
+        }
+
+        let entry = StrategyEntry {
+            name: name.to_string(),
+            params,
+            line: index + 1,
+        };
+        if name == STEP_COSTS.0 {
+            step_costs = StepCosts {
+                danger_cost: entry
+                    .param("danger_cost", step_costs.danger_cost),
+                crowd_cost: entry
+                    .param("crowd_cost", step_costs.crowd_cost),
+            };
+        } else {
+            entries.push(entry);
+        }
+    }
+
+    Ok(StrategyConfig {
+        entries,
+        step_costs,
+    })
+}
+
+/// Config of the default strategy stack, from strategies.conf.
+pub fn default_strategy_config() -> StrategyConfig {
+    parse_strategy_config(DEFAULT_STRATEGY_CONFIG)
+        .expect("valid default config")
+}
+
+/// Read and parse strategy config file.
+pub fn read_strategy_config(
+    path: &str,
+) -> Result<StrategyConfig, String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| format!("Can not read config '{}': {}", path, e))
+        .and_then(|text| parse_strategy_config(&text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world_step::*;
+
+    #[test]
+    fn parse_strategies_in_order() {
+        let config = parse_strategy_config(
+            "# Only food
+             gather_food cutoff_len=8   # near food
+
+             random_walk",
+        )
+        .expect("valid config");
+
+        let names: Vec<&str> =
+            config.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["gather_food", "random_walk"]);
+        assert_eq!(config.entries[0].params["cutoff_len"], 8);
+        assert_eq!(config.entries[0].param("max_result_len", 3), 3);
+    }
+
+    #[test]
+    fn parse_default_config() {
+        let config = default_strategy_config();
+
+        let names: Vec<&str> =
+            config.entries.iter().map(|e| e.name.as_str()).collect();
+        let known: Vec<&str> =
+            STRATEGY_PARAMS.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, known);
+    }
+
+    #[test]
+    fn parse_step_costs() {
+        let config = parse_strategy_config(
+            "step_costs danger_cost=7
+             random_walk",
+        )
+        .expect("valid config");
+
+        assert_eq!(config.entries.len(), 1);
+        assert_eq!(
+            config.step_costs,
+            StepCosts {
+                danger_cost: 7,
+                crowd_cost: StepCosts::default().crowd_cost,
+            }
+        );
+    }
+
+    #[test]
+    fn report_bad_configs() {
+        assert_eq!(
+            parse_strategy_config("battle\nflee"),
+            Err("Line 2: unknown strategy 'flee'".to_string())
+        );
+        assert_eq!(
+            parse_strategy_config("battle radius=3"),
+            Err("Line 1: unknown parameter 'radius' of battle"
+                .to_string())
+        );
+        assert_eq!(
+            parse_strategy_config("gather_food cutoff_len=0"),
+            Err(format!(
+                "Line 1: bad number '0' for cutoff_len, not in {:?}",
+                POSITIVE
+            ))
+        );
+        assert_eq!(
+            parse_strategy_config("spread_out_scout grid_step=65536"),
+            Err("Line 1: bad number '65536' for grid_step, not in \
+                 1..=65535"
+                .to_string())
+        );
+    }
+
+    #[test]
+    fn turn_off_step_costs() {
+        let config = parse_strategy_config(
+            "step_costs danger_cost=0 crowd_cost=0",
+        )
+        .expect("valid config");
+
+        assert_eq!(
+            config.step_costs,
+            StepCosts {
+                danger_cost: 0,
+                crowd_cost: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn report_unknown_strategy_of_built_config() {
+        let mut config =
+            parse_strategy_config("battle").expect("valid config");
+        config.entries.push(StrategyEntry {
+            name: "flee".to_string(),
+            params: HashMap::new(),
+            line: 3,
+        });
+
+        assert_eq!(
+            config.create(&GameParameters::default()).err(),
+            Some("Line 3: unknown strategy 'flee'".to_string())
+        );
+    }
+
+    #[test]
+    fn apply_configured_parameters() {
+        let world_step =
+            &BasicWorldStep::new_from_line_map("a----*----");
+        let mut near =
+            parse_strategy_config("gather_food cutoff_len=3")
+                .unwrap()
+                .create(&GameParameters::default())
+                .unwrap();
+        let mut far =
+            parse_strategy_config("gather_food cutoff_len=6")
+                .unwrap()
+                .create(&GameParameters::default())
+                .unwrap();
+
+        let apply = |strategy: &mut CompositeStrategy| {
+            let mut ants: HashSet<Position> = set![pos(0, 0)];
+            strategy.apply(world_step, &mut ants, &Deadline::never())
+        };
+
+        assert_eq!(apply(&mut near), vec![]);
+        assert_eq!(apply(&mut far), vec![pos(0, 0).east()]);
+    }
+}
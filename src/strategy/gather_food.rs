@@ -1,24 +1,152 @@
 use crate::strategy::*;
 use crate::world_step::*;
 
+use std::collections::HashMap;
 use std::collections::HashSet;
 
-pub struct GatherFood {}
+/// Name of the missions given by GatherFood.
+const MISSION: &str = "gather_food";
+
+pub struct GatherFood {
+    max_result_len: usize,
+    cutoff_len: usize,
+    paths: PathCache,
+}
+
+impl GatherFood {
+    pub fn new(
+        max_result_len: usize,
+        cutoff_len: usize,
+    ) -> GatherFood {
+        GatherFood {
+            max_result_len,
+            cutoff_len,
+            paths: PathCache::default(),
+        }
+    }
+
+    pub fn max_result_len(&self) -> usize {
+        self.max_result_len
+    }
+
+    pub fn cutoff_len(&self) -> usize {
+        self.cutoff_len
+    }
+
+    fn food_targets(
+        world_step: &dyn WorldStep,
+    ) -> HashMap<Position, usize> {
+        world_step
+            .get_positions(Tile::Food)
+            .into_iter()
+            .map(|food| (food, 1))
+            .collect()
+    }
+
+    /// Paths of the ants nearest to the food, reusing cached paths of
+    /// tracked ants, and otherwise following the food field of the
+    /// turn if flooded, or searching.
+    fn paths(
+        &mut self,
+        world_step: &dyn WorldStep,
+        ants_available: &mut HashSet<Position>,
+        tracker: &AntTracker,
+        deadline: &Deadline,
+    ) -> Vec<SearchResult> {
+        let targets = GatherFood::food_targets(world_step);
+        let max_result_len = self.max_result_len;
+        let cutoff_len = self.cutoff_len;
+
+        self.paths.best_paths(
+            &targets,
+            world_step,
+            ants_available,
+            tracker,
+            |capacities, ants| match world_step.distance_fields() {
+                Some(fields) => best_paths_along_field(
+                    &fields.food,
+                    capacities,
+                    world_step,
+                    ants,
+                    cutoff_len,
+                ),
+                None => best_paths_to_target_with_capacity(
+                    capacities,
+                    world_step,
+                    ants,
+                    max_result_len,
+                    cutoff_len,
+                    deadline,
+                ),
+            },
+        )
+    }
+}
+
+impl Default for GatherFood {
+    fn default() -> GatherFood {
+        GatherFood::new(3, 15)
+    }
+}
 
 impl Strategy for GatherFood {
     fn apply(
-        &self,
+        &mut self,
         world_step: &dyn WorldStep,
         ants_available: &mut HashSet<Position>,
+        deadline: &Deadline,
     ) -> Orders {
-        best_orders_to_target(
-            &world_step.get_positions(Tile::Food),
+        self.apply_tracked(
             world_step,
             ants_available,
-            3,
-            15,
+            &mut AntTracker::default(),
+            deadline,
         )
     }
+
+    /// Ants already on their way to food that is still there keep
+    /// going, one ant per food, before the rest of the food is
+    /// planned for.
+    fn apply_tracked(
+        &mut self,
+        world_step: &dyn WorldStep,
+        ants_available: &mut HashSet<Position>,
+        tracker: &mut AntTracker,
+        deadline: &Deadline,
+    ) -> Orders {
+        let mut ants: Vec<Position> =
+            ants_available.iter().cloned().collect();
+        ants.sort();
+        for ant in ants.iter() {
+            match (tracker.id(ant), tracker.mission(ant)) {
+                (Some(id), Some(m)) if m.strategy == MISSION => {
+                    self.paths.remember(id, m.path.clone())
+                }
+                _ => {}
+            }
+        }
+
+        let paths =
+            self.paths(world_step, ants_available, tracker, deadline);
+
+        for ant in ants.iter().filter(|a| !ants_available.contains(a))
+        {
+            tracker.clear_mission(ant);
+        }
+        let size = world_step.size();
+        let mut orders = vec![];
+        for path in paths {
+            if let Some(order) = path.first_order(size) {
+                tracker.set_mission(Mission::new(MISSION, path));
+                orders.push(order);
+            }
+        }
+        orders
+    }
+
+    fn cache_stats(&self) -> CacheStats {
+        self.paths.stats()
+    }
 }
 
 #[cfg(test)]
@@ -32,13 +160,14 @@ mod tests {
              -*-a--a-*-
              ----------",
         );
-        let strategy = &GatherFood {};
+        let strategy = &mut GatherFood::default();
 
         let left_ant = pos(1, 3);
         let right_ant = pos(1, 6);
         let mut ants = set![left_ant.clone(), right_ant.clone()];
 
-        let actual_orders = strategy.apply(world_step, &mut ants);
+        let actual_orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
 
         assert_eq![ants, set![]];
 
@@ -55,13 +184,14 @@ mod tests {
              -a---*--a-
              ----------",
         );
-        let strategy = &GatherFood {};
+        let strategy = &mut GatherFood::default();
 
         let left_ant = pos(1, 1);
         let right_ant = pos(1, 8);
         let mut ants = set![left_ant.clone(), right_ant.clone()];
 
-        let actual_orders = strategy.apply(world_step, &mut ants);
+        let actual_orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
 
         assert_eq![ants, set![left_ant]];
         assert_eq![actual_orders, vec![right_ant.west()]];
@@ -77,14 +207,90 @@ mod tests {
              ---------
              ----*----",
         );
-        let strategy = &GatherFood {};
+        let strategy = &mut GatherFood::default();
 
         let ant = pos(2, 4);
         let mut ants = set![ant.clone()];
 
-        let actual_orders = strategy.apply(world_step, &mut ants);
+        let actual_orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
 
         assert_eq![ants, set![]];
         assert_eq![actual_orders, vec![ant.north()]];
     }
+
+    #[test]
+    fn keep_going_to_food_of_mission() {
+        let world_step = &BasicWorldStep::new_from_line_map(
+            "----------
+             -*-a----*-
+             ----------",
+        );
+        let strategy = &mut GatherFood::default();
+        let ant = pos(1, 3);
+        let path = (4..=8)
+            .fold(SearchResult::start(ant.clone()), |r, col| {
+                r.add_step(pos(1, col))
+            });
+        let mut tracker = AntTracker::default();
+        tracker.update(&world_step.all_my_ants());
+        tracker.set_mission(Mission::new(MISSION, path));
+        let mut ants = set![ant.clone()];
+
+        let orders = strategy.apply_tracked(
+            world_step,
+            &mut ants,
+            &mut tracker,
+            &Deadline::never(),
+        );
+
+        assert_eq![orders, vec![ant.east()]];
+        assert_eq![ants, set![]];
+    }
+
+    #[test]
+    fn give_missions_to_ants_sent_to_food() {
+        let world_step = &BasicWorldStep::new_from_line_map(
+            "----------
+             -*-a----*-
+             ----------",
+        );
+        let strategy = &mut GatherFood::default();
+        let ant = pos(1, 3);
+        let mut tracker = AntTracker::default();
+        tracker.update(&world_step.all_my_ants());
+        let mut ants = set![ant.clone()];
+
+        let orders = strategy.apply_tracked(
+            world_step,
+            &mut ants,
+            &mut tracker,
+            &Deadline::never(),
+        );
+
+        assert_eq![orders, vec![ant.west()]];
+        let mission = tracker.mission(&ant).expect("mission");
+        assert_eq![mission.target, pos(1, 1)];
+        assert_eq![mission.path.order_length(), 2];
+    }
+
+    #[test]
+    fn follow_food_field_around_water() {
+        let world_step = &FieldLayer::new_from_line_map(
+            "--------
+             -a-%*-a-
+             --------
+             ---a----",
+            15,
+        );
+        let strategy = &mut GatherFood::default();
+        let mut ants = set![pos(1, 1), pos(1, 6), pos(3, 3)];
+
+        let orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
+
+        // Only the nearest ant goes, the others are left available.
+        assert_eq![orders, vec![pos(1, 6).west()]];
+        assert_eq![ants, set![pos(1, 1), pos(3, 3)]];
+    }
 }
@@ -0,0 +1,200 @@
+use crate::strategy::*;
+use crate::world_step::*;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+/// Defends own hills against enemy ants getting within the defend
+/// radius, the squared distance from own hill within which enemy
+/// ants are considered a threat. For each intruding enemy ant, one
+/// nearby ant is sent to intercept it and one ant stands guard on a
+/// diagonal of the hill.
+pub struct HillDefender {
+    defend_radius2: u32,
+}
+
+impl HillDefender {
+    pub fn new(defend_radius2: u32) -> HillDefender {
+        HillDefender { defend_radius2 }
+    }
+
+    pub fn defend_radius2(&self) -> u32 {
+        self.defend_radius2
+    }
+
+    /// Only ants within about twice the defend radius are near
+    /// enough to help.
+    fn cutoff_len(&self) -> usize {
+        2 * (f64::from(self.defend_radius2).sqrt().ceil() as usize)
+    }
+}
+
+impl Default for HillDefender {
+    fn default() -> HillDefender {
+        HillDefender::new(100)
+    }
+}
+
+/// Positions diagonal to the hill, not being water.
+fn guard_positions(
+    world_step: &dyn WorldStep,
+    hill: &Position,
+) -> Vec<Position> {
+    let size = world_step.size();
+    let north = hill.north().target_pos(size);
+    let south = hill.south().target_pos(size);
+
+    vec![
+        north.east().target_pos(size),
+        south.east().target_pos(size),
+        south.west().target_pos(size),
+        north.west().target_pos(size),
+    ]
+    .into_iter()
+    .filter(|p| world_step.tile(p) != Tile::Water)
+    .collect()
+}
+
+impl Strategy for HillDefender {
+    fn apply(
+        &mut self,
+        world_step: &dyn WorldStep,
+        ants_available: &mut HashSet<Position>,
+        deadline: &Deadline,
+    ) -> Orders {
+        let size = world_step.size();
+        let enemies = world_step.get_enemy_positions(TileKind::Ant);
+        let mut targets = HashMap::<Position, usize>::new();
+
+        for hill in world_step.get_positions(Tile::Hill(0)) {
+            let intruders: Vec<&Position> = enemies
+                .iter()
+                .filter(|e| {
+                    distance2(e, &hill, size) <= self.defend_radius2
+                })
+                .collect();
+
+            for intruder in intruders.iter() {
+                targets.insert((*intruder).clone(), 1);
+            }
+
+            let guards = guard_positions(world_step, &hill)
+                .into_iter()
+                .take(intruders.len());
+
+            for guard in guards {
+                // Ants already guarding stay where they are.
+                if !ants_available.remove(&guard) {
+                    targets.insert(guard, 1);
+                }
+            }
+        }
+
+        if targets.is_empty() {
+            return vec![];
+        }
+
+        best_orders_to_target_with_capacity(
+            &targets,
+            world_step,
+            ants_available,
+            3,
+            self.cutoff_len(),
+            deadline,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defend(
+        map: &'static str,
+        defend_radius2: u32,
+        ants: &mut HashSet<Position>,
+    ) -> HashSet<Order> {
+        let world_step = &BasicWorldStep::new_from_line_map(map);
+        let strategy = &mut HillDefender::new(defend_radius2);
+
+        strategy
+            .apply(world_step, ants, &Deadline::never())
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn ignore_enemies_far_from_hill() {
+        let ant = pos(1, 3);
+        let mut ants = set![ant.clone()];
+
+        let actual_orders = defend(
+            "------------------------
+             -0-a------b-------------
+             ------------------------",
+            25,
+            &mut ants,
+        );
+
+        assert_eq![ants, set![ant]];
+        assert_eq![actual_orders, set![]];
+    }
+
+    #[test]
+    fn intercept_enemy_approaching_hill() {
+        let ant = pos(1, 5);
+        let mut ants = set![ant.clone()];
+
+        let actual_orders = defend(
+            "------------
+             -0---a-b----
+             ------------",
+            49,
+            &mut ants,
+        );
+
+        assert_eq![ants, set![]];
+        assert_eq![actual_orders, set![ant.east()]];
+    }
+
+    #[test]
+    fn intercept_and_stand_guard_on_diagonal() {
+        let guard = pos(1, 2);
+        let interceptor = pos(1, 7);
+        let mut ants = set![guard.clone(), interceptor.clone()];
+
+        let actual_orders = defend(
+            "------------
+             -0a----ab---
+             ------------",
+            64,
+            &mut ants,
+        );
+
+        assert_eq![ants, set![]];
+        assert_eq![
+            actual_orders,
+            set![guard.north(), interceptor.east()]
+        ];
+    }
+
+    #[test]
+    fn keep_guarding_ant_in_place() {
+        let guard = pos(0, 2);
+        let far_ant = pos(1, 21);
+        let mut ants = set![guard.clone(), far_ant.clone()];
+
+        let actual_orders = defend(
+            "--a---------------------------
+             -0-----b-------------a--------
+             ------------------------------",
+            36,
+            &mut ants,
+        );
+
+        // Guard stays and the far away ant is out of reach, not
+        // being near enough to intercept.
+        assert_eq![ants, set![far_ant]];
+        assert_eq![actual_orders, set![]];
+    }
+}
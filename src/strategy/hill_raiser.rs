@@ -1,24 +1,184 @@
 use crate::strategy::*;
 use crate::world_step::*;
 
+use crate::strategy::search::HierarchicalSearch;
+use std::collections::HashMap;
 use std::collections::HashSet;
 
-pub struct HillRaiser {}
+/// Longest path of the raiders sent to enemy hills beyond the cutoff
+/// length, the distance across the largest maps of the game.
+const FAR_CUTOFF_LEN: usize = 200;
+
+/// Sends ants to raid enemy hills. Hills within the cutoff length
+/// get the nearest ants, and hills still lacking raiders get ants
+/// from further away, planned through the cluster graph of the far
+/// search.
+pub struct HillRaiser {
+    /// Max number of ants sent to raid the same enemy hill.
+    raiders_per_hill: usize,
+    max_result_len: usize,
+    cutoff_len: usize,
+    paths: PathCache,
+    /// Search of far hills, keeping its cluster graph between turns.
+    far_search: HierarchicalSearch,
+}
+
+impl HillRaiser {
+    pub fn new(
+        raiders_per_hill: usize,
+        max_result_len: usize,
+        cutoff_len: usize,
+    ) -> HillRaiser {
+        HillRaiser {
+            raiders_per_hill,
+            max_result_len,
+            cutoff_len,
+            paths: PathCache::default(),
+            far_search: HierarchicalSearch::default(),
+        }
+    }
+
+    pub fn raiders_per_hill(&self) -> usize {
+        self.raiders_per_hill
+    }
+
+    pub fn max_result_len(&self) -> usize {
+        self.max_result_len
+    }
+
+    pub fn cutoff_len(&self) -> usize {
+        self.cutoff_len
+    }
+
+    /// Paths of available ants to the hills not given enough raiders
+    /// by the near paths, searched beyond the cutoff length.
+    fn far_paths(
+        &self,
+        hills: &HashMap<Position, usize>,
+        near: &[SearchResult],
+        world_step: &dyn WorldStep,
+        ants_available: &mut HashSet<Position>,
+        deadline: &Deadline,
+    ) -> Vec<SearchResult> {
+        let mut capacities = hills.clone();
+        for path in near {
+            if let Some(left) = capacities.get_mut(&path.last_step())
+            {
+                *left = left.saturating_sub(1);
+            }
+        }
+        capacities.retain(|_, &mut left| left > 0);
+        if capacities.is_empty() || ants_available.is_empty() {
+            return vec![];
+        }
+
+        self.far_search.update_graph(world_step, deadline);
+        let results = ParallelMultiSearch {
+            assignment: Assignment::Greedy,
+            threads: default_search_threads(),
+            search: Box::new(&self.far_search),
+        }
+        .search_all_with_capacity(
+            world_step,
+            ants_available,
+            &capacities,
+            self.max_result_len,
+            FAR_CUTOFF_LEN,
+            deadline,
+        );
+
+        for result in &results {
+            ants_available.remove(&result.first_step());
+        }
+        results
+    }
+}
+
+impl Default for HillRaiser {
+    fn default() -> HillRaiser {
+        HillRaiser::new(3, 5, 20)
+    }
+}
 
 impl Strategy for HillRaiser {
     fn apply(
-        &self,
+        &mut self,
         world_step: &dyn WorldStep,
         ants_available: &mut HashSet<Position>,
+        deadline: &Deadline,
     ) -> Orders {
-        best_orders_to_target(
-            &world_step.get_positions(Tile::Hill(1)),
+        self.apply_tracked(
             world_step,
             ants_available,
-            5,
-            20,
+            &mut AntTracker::default(),
+            deadline,
         )
     }
+
+    /// Cached paths of tracked ants are reused before raiders are
+    /// planned for the remaining capacities of the hills.
+    fn apply_tracked(
+        &mut self,
+        world_step: &dyn WorldStep,
+        ants_available: &mut HashSet<Position>,
+        tracker: &mut AntTracker,
+        deadline: &Deadline,
+    ) -> Orders {
+        let hills: HashMap<Position, usize> = world_step
+            .get_enemy_positions(TileKind::Hill)
+            .into_iter()
+            .map(|hill| (hill, self.raiders_per_hill))
+            .collect();
+        let max_result_len = self.max_result_len;
+        let cutoff_len = self.cutoff_len;
+
+        // Following the enemy hill field of the turn if flooded.
+        let mut paths = self.paths.best_paths(
+            &hills,
+            world_step,
+            ants_available,
+            tracker,
+            |capacities, ants| match world_step.distance_fields() {
+                Some(fields) => best_paths_along_field(
+                    &fields.enemy_hills,
+                    capacities,
+                    world_step,
+                    ants,
+                    cutoff_len,
+                ),
+                None => best_paths_to_target_with_capacity(
+                    capacities,
+                    world_step,
+                    ants,
+                    max_result_len,
+                    cutoff_len,
+                    deadline,
+                ),
+            },
+        );
+
+        for path in self.far_paths(
+            &hills,
+            &paths,
+            world_step,
+            ants_available,
+            deadline,
+        ) {
+            if let Some(id) = tracker.id(&path.first_step()) {
+                self.paths.remember(id, path.clone());
+            }
+            paths.push(path);
+        }
+
+        paths
+            .iter()
+            .flat_map(|r| r.first_order(world_step.size()))
+            .collect()
+    }
+
+    fn cache_stats(&self) -> CacheStats {
+        self.paths.stats()
+    }
 }
 
 #[cfg(test)]
@@ -32,12 +192,13 @@ mod tests {
              -0-a--
              ------",
         );
-        let strategy = &HillRaiser {};
+        let strategy = &mut HillRaiser::default();
 
         let ant = pos(1, 3);
         let mut ants = set![ant.clone()];
 
-        let actual_orders = strategy.apply(world_step, &mut ants);
+        let actual_orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
 
         assert_eq![ants, set![ant]];
 
@@ -51,13 +212,14 @@ mod tests {
              -1-a--a-1-
              ----------",
         );
-        let strategy = &HillRaiser {};
+        let strategy = &mut HillRaiser::default();
 
         let left_ant = pos(1, 3);
         let right_ant = pos(1, 6);
         let mut ants = set![left_ant.clone(), right_ant.clone()];
 
-        let actual_orders = strategy.apply(world_step, &mut ants);
+        let actual_orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
 
         assert_eq![ants, set![]];
 
@@ -68,22 +230,71 @@ mod tests {
     }
 
     #[test]
-    fn orders_limited_by_hills_so_use_nearest_ant() {
+    fn several_ants_raid_same_hill() {
         let world_step = &mut BasicWorldStep::new_from_line_map(
             "----------
              -a---1--a-
              ----------",
         );
-        let strategy = &HillRaiser {};
+        let strategy = &mut HillRaiser::default();
 
         let left_ant = pos(1, 1);
         let right_ant = pos(1, 8);
         let mut ants = set![left_ant.clone(), right_ant.clone()];
 
-        let actual_orders = strategy.apply(world_step, &mut ants);
+        let actual_orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
+
+        assert_eq![ants, set![]];
+        assert_eq![
+            actual_orders,
+            vec![right_ant.west(), left_ant.east()]
+        ];
+    }
+
+    #[test]
+    fn raiders_limited_per_hill_so_use_nearest_ants() {
+        let world_step = &mut BasicWorldStep::new_from_line_map(
+            "--------------
+             a-a--1--a---a-
+             --------------",
+        );
+        let strategy = &mut HillRaiser::default();
+
+        let far_ant = pos(1, 12);
+        let mut ants =
+            set![pos(1, 0), pos(1, 2), pos(1, 8), far_ant.clone()];
+
+        let actual_orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
+
+        assert_eq![ants, set![far_ant]];
+        assert_eq![actual_orders.len(), strategy.raiders_per_hill()];
+    }
+
+    #[test]
+    fn raid_hills_of_all_enemies() {
+        let world_step = &mut BasicWorldStep::new_from_line_map(
+            "----------
+             -2-a--a-5-
+             ----------",
+        );
+        let strategy = &mut HillRaiser::default();
+
+        let left_ant = pos(1, 3);
+        let right_ant = pos(1, 6);
+        let mut ants = set![left_ant.clone(), right_ant.clone()];
+
+        let actual_orders: HashSet<Order> = strategy
+            .apply(world_step, &mut ants, &Deadline::never())
+            .into_iter()
+            .collect();
 
-        assert_eq![ants, set![left_ant]];
-        assert_eq![actual_orders, vec![right_ant.west()]];
+        assert_eq![ants, set![]];
+        assert_eq![
+            actual_orders,
+            set![left_ant.west(), right_ant.east()]
+        ];
     }
 
     #[test]
@@ -96,14 +307,34 @@ mod tests {
              ---------
              ----1----",
         );
-        let strategy = &HillRaiser {};
+        let strategy = &mut HillRaiser::default();
 
         let ant = pos(2, 4);
         let mut ants = set![ant.clone()];
 
-        let actual_orders = strategy.apply(world_step, &mut ants);
+        let actual_orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
 
         assert_eq![ants, set![]];
         assert_eq![actual_orders, vec![ant.north()]];
     }
+
+    #[test]
+    fn raid_hills_beyond_cutoff_length() {
+        let world_step = &AvoidWaterFilter::new_from_line_map(
+            "-------%------------
+             -a-----%-------1----
+             -------%------------
+             --------------------",
+        );
+        let strategy = &mut HillRaiser::new(1, 5, 5);
+        let mut ants = set![pos(1, 1)];
+
+        let orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
+
+        // West across the border, 6 steps beyond the wall.
+        assert_eq![orders, vec![pos(1, 1).west()]];
+        assert_eq![ants, set![]];
+    }
 }
@@ -1,54 +1,142 @@
+use crate::ant_tracker::*;
+use crate::deadline::Deadline;
 use crate::strategy::multisearch::*;
+use crate::strategy::search::{DistanceField, SearchResult};
 use crate::world_step::WorldStep;
 use ants_ai_challenge_api::*;
+use std::collections::HashMap;
 use std::collections::HashSet;
-use std::iter::FromIterator;
 
+pub mod battle;
 pub mod composite_strategy;
+pub mod config;
 pub mod gather_food;
+pub mod hill_defender;
 pub mod hill_raiser;
 pub mod multisearch;
+pub mod path_cache;
 pub mod random_walk;
 pub mod search;
 pub mod spread_out_scout;
 
+pub use battle::*;
 pub use composite_strategy::*;
+pub use config::*;
 pub use gather_food::*;
+pub use hill_defender::*;
 pub use hill_raiser::*;
+pub use path_cache::*;
 pub use random_walk::*;
 pub use spread_out_scout::*;
 
-pub trait Strategy {
+/// Strategies are sent to the worker thread planning the turn, see
+/// `planner`.
+pub trait Strategy: Send {
     fn apply(
-        &self,
+        &mut self,
         world_step: &dyn WorldStep,
         ants_available: &mut HashSet<Position>,
+        deadline: &Deadline,
     ) -> Orders;
+
+    /// Like apply, but with the tracked ants of the agent, letting
+    /// the strategy keep missions of ants across turns.
+    fn apply_tracked(
+        &mut self,
+        world_step: &dyn WorldStep,
+        ants_available: &mut HashSet<Position>,
+        _tracker: &mut AntTracker,
+        deadline: &Deadline,
+    ) -> Orders {
+        self.apply(world_step, ants_available, deadline)
+    }
+
+    /// Usage of path caches of the strategy, if any.
+    fn cache_stats(&self) -> CacheStats {
+        CacheStats::default()
+    }
 }
 
-fn best_orders_to_target(
-    targets: &[Position],
+/// Orders of the ants nearest to the targets, up to the given
+/// capacity of ants per target.
+fn best_orders_to_target_with_capacity(
+    targets: &HashMap<Position, usize>,
     world_step: &dyn WorldStep,
     ants_available: &mut HashSet<Position>,
     max_result_len: usize,
     cutoff_len: usize,
+    deadline: &Deadline,
 ) -> Orders {
-    let target_set = HashSet::from_iter(targets.iter().cloned());
-
-    let results = create_multisearch().search_all(
+    best_paths_to_target_with_capacity(
+        targets,
         world_step,
         ants_available,
-        &target_set,
         max_result_len,
         cutoff_len,
-    );
+        deadline,
+    )
+    .iter()
+    .flat_map(|r| r.first_order(world_step.size()))
+    .collect()
+}
+
+/// Like best_orders_to_target_with_capacity, but gives the paths
+/// from each chosen ant to its target.
+fn best_paths_to_target_with_capacity(
+    targets: &HashMap<Position, usize>,
+    world_step: &dyn WorldStep,
+    ants_available: &mut HashSet<Position>,
+    max_result_len: usize,
+    cutoff_len: usize,
+    deadline: &Deadline,
+) -> Vec<SearchResult> {
+    let results = create_multisearch(Assignment::Greedy)
+        .search_all_with_capacity(
+            world_step,
+            ants_available,
+            targets,
+            max_result_len,
+            cutoff_len,
+            deadline,
+        );
 
     for result in &results {
         ants_available.remove(&result.first_step());
     }
-
     results
+}
+
+/// Like best_paths_to_target_with_capacity, but following the
+/// gradient of a distance field flooded from the targets, nearest
+/// ants first. An ant is left available if the target nearest to it
+/// has no capacity left.
+fn best_paths_along_field(
+    field: &DistanceField,
+    targets: &HashMap<Position, usize>,
+    world_step: &dyn WorldStep,
+    ants_available: &mut HashSet<Position>,
+    cutoff_len: usize,
+) -> Vec<SearchResult> {
+    let mut ants: Vec<(usize, Position)> = ants_available
         .iter()
-        .flat_map(|r| r.first_order(world_step.size()))
-        .collect()
+        .flat_map(|ant| field.distance(ant).map(|d| (d, ant.clone())))
+        .filter(|&(d, _)| d > 0 && d <= cutoff_len)
+        .collect();
+    ants.sort();
+
+    let mut capacities = targets.clone();
+    let mut results = vec![];
+    for (_, ant) in ants {
+        let path = match field.path(world_step, &ant) {
+            Some(path) => path,
+            None => continue,
+        };
+        match capacities.get_mut(&path.last_step()) {
+            Some(left) if *left > 0 => *left -= 1,
+            _ => continue,
+        }
+        ants_available.remove(&ant);
+        results.push(path);
+    }
+    results
 }
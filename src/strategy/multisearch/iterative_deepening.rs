@@ -0,0 +1,238 @@
+use crate::strategy::multisearch::*;
+use crate::strategy::search::manhattan_filter::manhattan;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Search length cutoff used in the first real search iteration.
+const FIRST_CUTOFF_LEN: usize = 4;
+
+/// Search iterations double the cutoff and tend to take at least
+/// twice as long as the previous iteration. The next iteration is
+/// only started if three times the previous one fits before the
+/// deadline, leaving a margin for searches growing faster than that.
+const ITERATION_GROWTH_FACTOR: u32 = 3;
+
+/// Iteratively increases the search length cutoff of the inner
+/// multi search, until either the given cutoff is reached or the
+/// deadline is near. The first pass only uses Manhattan distances,
+/// ignoring all obstacles except for the first step, giving a rough
+/// result if there is no time for anything else.
+///
+/// The result of the last iteration completed before the deadline is
+/// used. An iteration interrupted by the deadline is only used if it
+/// has at least as many results as the previous one.
+pub struct IterativeDeepening<M: MultiSearch> {
+    pub inner: M,
+}
+
+/// Straight path from 'from' to 'to', taking the given first step
+/// and then moving along rows and finally along columns.
+fn straight_path(
+    from: &Position,
+    first_dir: Direction,
+    to: &Position,
+    size: &Position,
+) -> SearchResult {
+    let mut current = from.order(first_dir).target_pos(size);
+    let mut result =
+        SearchResult::start(from.clone()).add_step(current.clone());
+
+    while current != *to {
+        let dir = if current.row != to.row {
+            closer_direction(&current, to, size, &[North, South])
+        } else {
+            closer_direction(&current, to, size, &[East, West])
+        };
+        current = current.order(dir).target_pos(size);
+        result = result.add_step(current.clone());
+    }
+    result
+}
+
+/// Direction among given directions getting closest to target.
+fn closer_direction(
+    from: &Position,
+    to: &Position,
+    size: &Position,
+    dirs: &[Direction],
+) -> Direction {
+    *dirs
+        .iter()
+        .min_by_key(|&&dir| {
+            manhattan(&from.order(dir).target_pos(size), to, size)
+        })
+        .expect("at least one direction")
+}
+
+/// Estimate results using Manhattan distance only. The first step
+/// of each result must be an available direction reducing the
+/// distance to the target.
+fn manhattan_pass(
+    world: &dyn WorldStep,
+    froms: &HashSet<Position>,
+    tos: &HashMap<Position, usize>,
+    max_result_len: usize,
+    cutoff_len: usize,
+) -> Vec<SearchResult> {
+    let size = world.size();
+    let mut all_results = vec![];
+
+    for (to, &capacity) in tos {
+        let mut results: Vec<SearchResult> = froms
+            .iter()
+            .filter(|from| {
+                manhattan(from, to, size) as usize <= cutoff_len
+            })
+            .flat_map(|from| {
+                let distance = manhattan(from, to, size);
+                world
+                    .available_directions(from)
+                    .into_iter()
+                    .find(|&dir| {
+                        let next = from.order(dir).target_pos(size);
+                        manhattan(&next, to, size) < distance
+                    })
+                    .map(|dir| straight_path(from, dir, to, size))
+            })
+            .collect();
+
+        results.sort_by_key(|r| r.order_length());
+        results.truncate(std::cmp::max(max_result_len, capacity));
+        all_results.extend(results);
+    }
+
+    GreedyDistance::greedily_choose(all_results, tos)
+}
+
+impl<M: MultiSearch> MultiSearch for IterativeDeepening<M> {
+    fn search_all_with_capacity(
+        &self,
+        world: &dyn WorldStep,
+        froms: &HashSet<Position>,
+        tos: &HashMap<Position, usize>,
+        max_result_len: usize,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> Vec<SearchResult> {
+        let mut best = manhattan_pass(
+            world,
+            froms,
+            tos,
+            max_result_len,
+            cutoff_len,
+        );
+
+        let mut iteration_cutoff_len =
+            std::cmp::min(FIRST_CUTOFF_LEN, cutoff_len);
+
+        while !deadline.is_passed() {
+            let start = Instant::now();
+
+            let results = self.inner.search_all_with_capacity(
+                world,
+                froms,
+                tos,
+                max_result_len,
+                iteration_cutoff_len,
+                deadline,
+            );
+
+            let completed = !deadline.is_passed();
+            if completed || results.len() >= best.len() {
+                best = results;
+            }
+
+            if !completed
+                || iteration_cutoff_len >= cutoff_len
+                || deadline.is_near(
+                    start.elapsed() * ITERATION_GROWTH_FACTOR,
+                )
+            {
+                break;
+            }
+
+            iteration_cutoff_len =
+                std::cmp::min(iteration_cutoff_len * 2, cutoff_len);
+        }
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::*;
+    use crate::world_step::AvoidWaterFilter;
+    use std::time::Duration;
+
+    fn search_with_deadline(
+        map: &'static str,
+        deadline: &Deadline,
+    ) -> Vec<SearchResult> {
+        let world_step = &AvoidWaterFilter::new_from_line_map(map);
+
+        IterativeDeepening {
+            inner: GreedyDistance {},
+        }
+        .search_all(
+            world_step,
+            &positions_of('a', map),
+            &positions_of('*', map),
+            10,
+            30,
+            deadline,
+        )
+    }
+
+    #[test]
+    fn use_manhattan_when_out_of_time() {
+        let map = "---------
+                   a--%*----
+                   ---------";
+
+        let actual = search_with_deadline(
+            map,
+            &Deadline::after(Duration::from_millis(0)),
+        );
+
+        // Path straight through water, since only Manhattan
+        // distance is used.
+        assert_eq!(1, actual.len());
+        assert_eq!(4, actual[0].order_length());
+        assert_eq!(
+            Some(pos(1, 0).east()),
+            actual[0].first_order(&size_of_world(map))
+        );
+    }
+
+    #[test]
+    fn same_as_inner_search_when_there_is_time() {
+        let map = "------------
+                   -a-%*-------
+                   ---%%----a--
+                   ---------*--";
+
+        let world_step = &AvoidWaterFilter::new_from_line_map(map);
+
+        let expected: HashSet<SearchResult> = GreedyDistance {}
+            .search_all(
+                world_step,
+                &positions_of('a', map),
+                &positions_of('*', map),
+                10,
+                30,
+                &Deadline::never(),
+            )
+            .into_iter()
+            .collect();
+
+        let actual: HashSet<SearchResult> =
+            search_with_deadline(map, &Deadline::never())
+                .into_iter()
+                .collect();
+
+        assert_eq!(expected.len(), 2);
+        assert_eq!(actual, expected);
+    }
+}
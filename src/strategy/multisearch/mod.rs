@@ -1,8 +1,17 @@
 use crate::strategy::search::SearchResult;
 use crate::strategy::*;
 use std::collections::BinaryHeap;
+use std::collections::HashMap;
 use std::collections::HashSet;
 
+pub mod iterative_deepening;
+pub mod optimal_assignment;
+pub mod parallel;
+
+pub use iterative_deepening::*;
+pub use optimal_assignment::*;
+pub use parallel::*;
+
 /// Search from multiple origins to multiple target.
 pub trait MultiSearch {
     /// Search nearest orders from 'from' to 'to'. The search can
@@ -15,40 +24,127 @@ pub trait MultiSearch {
         to: &HashSet<Position>,
         max_result_len: usize,
         cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> Vec<SearchResult> {
+        let capacities = to.iter().map(|t| (t.clone(), 1)).collect();
+
+        self.search_all_with_capacity(
+            world,
+            from,
+            &capacities,
+            max_result_len,
+            cutoff_len,
+            deadline,
+        )
+    }
+
+    /// Search nearest orders from 'from' to 'to', where each target
+    /// in 'to' can be assigned up to the given capacity of origins,
+    /// e.g. several ants raiding the same hill.
+    fn search_all_with_capacity(
+        &self,
+        world: &dyn WorldStep,
+        from: &HashSet<Position>,
+        to: &HashMap<Position, usize>,
+        max_result_len: usize,
+        cutoff_len: usize,
+        deadline: &Deadline,
     ) -> Vec<SearchResult>;
 }
 
-/// Create default search algorithms
-pub fn create_multisearch() -> Box<dyn MultiSearch> {
-    Box::new(GreedyDistance {})
+/// Method used for assigning sources to targets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Assignment {
+    /// Shortest distance first, see GreedyDistance.
+    Greedy,
+    /// Minimal total distance, see OptimalAssignment.
+    Optimal,
+}
+
+/// Create default search algorithms, using given assignment method
+/// and searching targets in parallel.
+pub fn create_multisearch(
+    assignment: Assignment,
+) -> Box<dyn MultiSearch> {
+    Box::new(IterativeDeepening {
+        inner: ParallelMultiSearch {
+            assignment,
+            threads: default_search_threads(),
+            search: crate::strategy::search::create_search(),
+        },
+    })
+}
+
+/// Search from all sources to each of the targets, until the
+/// deadline is passed. At least as many results as the capacity of
+/// the target are sought.
+fn search_each_target(
+    world: &dyn WorldStep,
+    froms: &HashSet<Position>,
+    tos: &HashMap<Position, usize>,
+    max_result_len: usize,
+    cutoff_len: usize,
+    deadline: &Deadline,
+) -> Vec<SearchResult> {
+    let single_target_search =
+        crate::strategy::search::create_search();
+
+    tos.iter()
+        .take_while(|_| !deadline.is_passed())
+        .flat_map(|(to, &capacity)| {
+            single_target_search.search(
+                world,
+                froms,
+                to.clone(),
+                std::cmp::max(max_result_len, capacity),
+                cutoff_len,
+                deadline,
+            )
+        })
+        .collect()
+}
+
+/// Capacity of given target, defaults to one.
+fn capacity_of(
+    to: &Position,
+    capacities: &HashMap<Position, usize>,
+) -> usize {
+    capacities.get(to).cloned().unwrap_or(1)
 }
 
 /// Determines orders between sources and targets by greedily
 /// choosing the shortest distance first until either all
-/// targets or all surces are included in choosen orders.
+/// targets (up to their capacity) or all surces are included in
+/// choosen orders.
 struct GreedyDistance {}
 
 impl GreedyDistance {
     /// Given a set of search results, greedily choose the
-    /// shortest ones, where to or from is not already choosen.
-    fn greedily_choose(all: Vec<SearchResult>) -> Vec<SearchResult> {
-        let prio_queue = BinaryHeap::from(all);
-        let mut choosen_tos = HashSet::<Position>::new();
+    /// shortest ones, where from is not already choosen and to is
+    /// not already choosen up to its capacity.
+    fn greedily_choose(
+        all: Vec<SearchResult>,
+        capacities: &HashMap<Position, usize>,
+    ) -> Vec<SearchResult> {
+        let mut prio_queue = BinaryHeap::from(all);
+        let mut choosen_tos = HashMap::<Position, usize>::new();
         let mut choosen_froms = HashSet::<Position>::new();
         let mut results = Vec::<SearchResult>::new();
 
-        for result in prio_queue {
+        while let Some(result) = prio_queue.pop() {
             let to = result.last_step();
             let from = result.first_step();
 
-            let is_from_or_to_allready_choosen = choosen_tos
-                .contains(&to)
+            let to_count = choosen_tos.get(&to).cloned().unwrap_or(0);
+
+            let is_from_or_to_allready_choosen = to_count
+                >= capacity_of(&to, capacities)
                 || choosen_froms.contains(&from);
 
             if !is_from_or_to_allready_choosen {
                 results.push(result);
                 choosen_froms.insert(from);
-                choosen_tos.insert(to);
+                choosen_tos.insert(to, to_count + 1);
             }
         }
 
@@ -57,32 +153,25 @@ impl GreedyDistance {
 }
 
 impl MultiSearch for GreedyDistance {
-    fn search_all(
+    fn search_all_with_capacity(
         &self,
         world: &dyn WorldStep,
         froms: &HashSet<Position>,
-        tos: &HashSet<Position>,
+        tos: &HashMap<Position, usize>,
         max_result_len: usize,
         cutoff_len: usize,
+        deadline: &Deadline,
     ) -> Vec<SearchResult> {
-        let single_target_search =
-            crate::strategy::search::create_search();
+        let all_results = search_each_target(
+            world,
+            froms,
+            tos,
+            max_result_len,
+            cutoff_len,
+            deadline,
+        );
 
-        let all_results = tos
-            .iter()
-            .cloned()
-            .flat_map(|to| {
-                single_target_search.search(
-                    world,
-                    froms,
-                    to,
-                    max_result_len,
-                    cutoff_len,
-                )
-            })
-            .collect::<Vec<SearchResult>>();
-
-        GreedyDistance::greedily_choose(all_results)
+        GreedyDistance::greedily_choose(all_results, tos)
     }
 }
 
@@ -102,13 +191,14 @@ mod tests {
 
         let world_step = &AvoidWaterFilter::new_from_line_map(map);
 
-        let actual_orders = create_multisearch()
+        let actual_orders = create_multisearch(Assignment::Greedy)
             .search_all(
                 world_step,
                 &positions_of('a', map),
                 &positions_of('*', map),
                 100,
                 100,
+                &Deadline::never(),
             )
             .iter()
             .flat_map(|sr| sr.first_order(world_step.size()))
@@ -149,6 +239,35 @@ mod tests {
         )
     }
 
+    #[test]
+    fn search_several_ants_to_target_with_capacity() {
+        let map = "-a-a*-a-----a--%";
+        let world_step = &AvoidWaterFilter::new_from_line_map(map);
+        let capacities = [(pos(0, 4), 3)].iter().cloned().collect();
+
+        let actual_orders = create_multisearch(Assignment::Greedy)
+            .search_all_with_capacity(
+                world_step,
+                &positions_of('a', map),
+                &capacities,
+                100,
+                100,
+                &Deadline::never(),
+            )
+            .iter()
+            .flat_map(|sr| sr.first_order(world_step.size()))
+            .collect::<HashSet<Order>>();
+
+        assert_eq![
+            actual_orders,
+            set![
+                pos(0, 1).east(),
+                pos(0, 3).east(),
+                pos(0, 6).west()
+            ]
+        ];
+    }
+
     #[test]
     fn search_finds_nearest_target_greedy_order() {
         assert_ant_a_to_food_orders(
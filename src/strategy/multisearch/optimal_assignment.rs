@@ -0,0 +1,285 @@
+use crate::strategy::multisearch::*;
+use std::collections::HashMap;
+
+/// Determines orders between sources and targets by solving the
+/// assignment problem, minimizing the total distance of the chosen
+/// orders. As many sources and targets as possible are assigned,
+/// using the Hungarian algorithm on the distance matrix. A target
+/// with capacity above one is represented by several columns in the
+/// matrix, one per assignable source.
+pub struct OptimalAssignment {}
+
+/// Cost of an assignment without any search result, large enough to
+/// never be chosen over assignments with results.
+fn missing_cost(all: &[SearchResult]) -> i64 {
+    all.iter().map(|r| r.order_length() as i64).sum::<i64>() + 1
+}
+
+/// Solves the assignment problem for given cost matrix with no more
+/// rows than columns, returning the assigned column for each row.
+fn hungarian(cost: &[Vec<i64>]) -> Vec<usize> {
+    let rows = cost.len();
+    let cols = cost.first().map_or(0, |row| row.len());
+    let infinity = i64::MAX / 4;
+
+    // Potentials and assignments, one-indexed with index 0 as
+    // a virtual column used when augmenting.
+    let mut u = vec![0_i64; rows + 1];
+    let mut v = vec![0_i64; cols + 1];
+    let mut row_of_col = vec![0_usize; cols + 1];
+    let mut way = vec![0_usize; cols + 1];
+
+    for row in 1..=rows {
+        row_of_col[0] = row;
+        let mut col0 = 0;
+        let mut min_v = vec![infinity; cols + 1];
+        let mut used = vec![false; cols + 1];
+
+        loop {
+            used[col0] = true;
+            let row0 = row_of_col[col0];
+            let mut delta = infinity;
+            let mut col1 = 0;
+
+            for col in 1..=cols {
+                if !used[col] {
+                    let current =
+                        cost[row0 - 1][col - 1] - u[row0] - v[col];
+                    if current < min_v[col] {
+                        min_v[col] = current;
+                        way[col] = col0;
+                    }
+                    if min_v[col] < delta {
+                        delta = min_v[col];
+                        col1 = col;
+                    }
+                }
+            }
+
+            for col in 0..=cols {
+                if used[col] {
+                    u[row_of_col[col]] += delta;
+                    v[col] -= delta;
+                } else {
+                    min_v[col] -= delta;
+                }
+            }
+
+            col0 = col1;
+            if row_of_col[col0] == 0 {
+                break;
+            }
+        }
+
+        loop {
+            let col1 = way[col0];
+            row_of_col[col0] = row_of_col[col1];
+            col0 = col1;
+            if col0 == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut result = vec![0; rows];
+    for col in 1..=cols {
+        if row_of_col[col] != 0 {
+            result[row_of_col[col] - 1] = col - 1;
+        }
+    }
+    result
+}
+
+/// Sorted and indexed positions.
+fn indexed(
+    positions: Vec<Position>,
+) -> (Vec<Position>, HashMap<Position, usize>) {
+    let mut sorted = positions;
+    sorted.sort();
+    sorted.dedup();
+    let index = sorted
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(i, p)| (p, i))
+        .collect();
+    (sorted, index)
+}
+
+impl OptimalAssignment {
+    /// Given a set of search results, choose the ones giving the
+    /// largest number of assignments with the minimal total length.
+    pub(super) fn optimally_choose(
+        all: Vec<SearchResult>,
+        capacities: &HashMap<Position, usize>,
+    ) -> Vec<SearchResult> {
+        let (froms, from_index) =
+            indexed(all.iter().map(|r| r.first_step()).collect());
+        let (tos, _) =
+            indexed(all.iter().map(|r| r.last_step()).collect());
+
+        // Each target slot is a column in the cost matrix.
+        let slots: Vec<Position> = tos
+            .iter()
+            .flat_map(|to| {
+                (0..capacity_of(to, capacities))
+                    .map(move |_| to.clone())
+            })
+            .collect();
+
+        // Shortest result for each pair of source and target.
+        let mut shortest: HashMap<
+            (Position, Position),
+            SearchResult,
+        > = HashMap::new();
+        for result in all.iter() {
+            let key = (result.first_step(), result.last_step());
+            let is_shorter = match shortest.get(&key) {
+                Some(r) => result.order_length() < r.order_length(),
+                None => true,
+            };
+            if is_shorter {
+                shortest.insert(key, result.clone());
+            }
+        }
+
+        // The assignment is solved with sources as rows, unless
+        // there are more sources than target slots.
+        let transposed = froms.len() > slots.len();
+        let (rows, cols) = if transposed {
+            (slots.len(), froms.len())
+        } else {
+            (froms.len(), slots.len())
+        };
+
+        let missing = missing_cost(&all);
+        let mut cost = vec![vec![missing; cols]; rows];
+
+        for (slot, to) in slots.iter().enumerate() {
+            for (from_pos, from) in from_index.iter() {
+                let key = (from_pos.clone(), to.clone());
+                if let Some(result) = shortest.get(&key) {
+                    let (row, col) = if transposed {
+                        (slot, *from)
+                    } else {
+                        (*from, slot)
+                    };
+                    cost[row][col] = result.order_length() as i64;
+                }
+            }
+        }
+
+        hungarian(&cost)
+            .into_iter()
+            .enumerate()
+            .flat_map(|(row, col)| {
+                let (from, slot) =
+                    if transposed { (col, row) } else { (row, col) };
+                shortest.remove(&(
+                    froms[from].clone(),
+                    slots[slot].clone(),
+                ))
+            })
+            .collect()
+    }
+}
+
+impl MultiSearch for OptimalAssignment {
+    fn search_all_with_capacity(
+        &self,
+        world: &dyn WorldStep,
+        froms: &HashSet<Position>,
+        tos: &HashMap<Position, usize>,
+        max_result_len: usize,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> Vec<SearchResult> {
+        let all_results = search_each_target(
+            world,
+            froms,
+            tos,
+            max_result_len,
+            cutoff_len,
+            deadline,
+        );
+
+        OptimalAssignment::optimally_choose(all_results, tos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::*;
+    use crate::world_step::AvoidWaterFilter;
+
+    fn total_length(
+        map: &'static str,
+        assignment: Assignment,
+    ) -> (usize, HashSet<Order>) {
+        let world_step = &AvoidWaterFilter::new_from_line_map(map);
+
+        let results = create_multisearch(assignment).search_all(
+            world_step,
+            &positions_of('a', map),
+            &positions_of('*', map),
+            100,
+            100,
+            &Deadline::never(),
+        );
+
+        let orders = results
+            .iter()
+            .flat_map(|sr| sr.first_order(world_step.size()))
+            .collect();
+
+        (results.iter().map(|r| r.order_length()).sum(), orders)
+    }
+
+    #[test]
+    fn hungarian_minimal_cost() {
+        assert_eq!(
+            hungarian(&[vec![4, 1, 3], vec![2, 0, 5], vec![3, 2, 2]]),
+            vec![1, 0, 2]
+        );
+    }
+
+    #[test]
+    fn hungarian_more_columns_than_rows() {
+        assert_eq!(
+            hungarian(&[vec![5, 1, 9], vec![1, 2, 9]]),
+            vec![1, 0]
+        );
+    }
+
+    #[test]
+    fn better_than_greedy_with_interleaved_ants_and_food() {
+        let map = "a-*a--*-------------";
+
+        let (greedy_length, _) =
+            total_length(map, Assignment::Greedy);
+        let (optimal_length, optimal_orders) =
+            total_length(map, Assignment::Optimal);
+
+        assert_eq!(greedy_length, 7);
+        assert_eq!(optimal_length, 5);
+        assert_eq!(
+            optimal_orders,
+            set![pos(0, 0).east(), pos(0, 3).east()]
+        );
+    }
+
+    #[test]
+    fn assign_as_many_as_possible() {
+        let map = "*a*a----------";
+
+        let (optimal_length, optimal_orders) =
+            total_length(map, Assignment::Optimal);
+
+        assert_eq!(optimal_length, 2);
+        assert_eq!(
+            optimal_orders,
+            set![pos(0, 1).west(), pos(0, 3).west()]
+        );
+    }
+}
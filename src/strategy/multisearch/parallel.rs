@@ -0,0 +1,196 @@
+use crate::strategy::multisearch::*;
+use crate::strategy::search::Search;
+use std::collections::HashMap;
+use std::thread;
+
+/// Upper limit of search threads, more threads tend to cost more in
+/// coordination than they save on the maps of the game.
+const MAX_SEARCH_THREADS: usize = 4;
+
+/// Number of search threads to use on this machine.
+pub fn default_search_threads() -> usize {
+    thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(MAX_SEARCH_THREADS)
+}
+
+/// Searches each target with the given search on scoped threads,
+/// and then assigns sources to targets with the given method, see
+/// GreedyDistance and OptimalAssignment. The search may be borrowed,
+/// e.g. a search kept by a strategy between turns.
+pub struct ParallelMultiSearch<'a> {
+    pub assignment: Assignment,
+    /// Number of threads the targets are split between, at most
+    /// MAX_SEARCH_THREADS.
+    pub threads: usize,
+    pub search: Box<dyn Search + 'a>,
+}
+
+impl<'a> ParallelMultiSearch<'a> {
+    /// Like search_each_target, but searching the targets in chunks,
+    /// one scoped thread per chunk. Targets are split into
+    /// consecutive chunks in iteration order and the results joined
+    /// in the same order, giving the same results as the sequential
+    /// search.
+    pub fn search_each_target(
+        &self,
+        world: &dyn WorldStep,
+        froms: &HashSet<Position>,
+        tos: &HashMap<Position, usize>,
+        max_result_len: usize,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> Vec<SearchResult> {
+        let targets: Vec<(&Position, &usize)> = tos.iter().collect();
+        let threads = self.threads.clamp(1, MAX_SEARCH_THREADS);
+        let chunk_len = targets.len().div_ceil(threads).max(1);
+        let search = &self.search;
+
+        let search_chunk = |chunk: &[(&Position, &usize)]| {
+            chunk
+                .iter()
+                .take_while(|_| !deadline.is_passed())
+                .flat_map(|(to, &capacity)| {
+                    search.search(
+                        world,
+                        froms,
+                        (*to).clone(),
+                        std::cmp::max(max_result_len, capacity),
+                        cutoff_len,
+                        deadline,
+                    )
+                })
+                .collect::<Vec<SearchResult>>()
+        };
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = targets
+                .chunks(chunk_len)
+                .map(|chunk| scope.spawn(move || search_chunk(chunk)))
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| {
+                    handle.join().expect("search thread")
+                })
+                .collect()
+        })
+    }
+}
+
+impl<'a> MultiSearch for ParallelMultiSearch<'a> {
+    fn search_all_with_capacity(
+        &self,
+        world: &dyn WorldStep,
+        froms: &HashSet<Position>,
+        tos: &HashMap<Position, usize>,
+        max_result_len: usize,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> Vec<SearchResult> {
+        let all_results = self.search_each_target(
+            world,
+            froms,
+            tos,
+            max_result_len,
+            cutoff_len,
+            deadline,
+        );
+
+        match self.assignment {
+            Assignment::Greedy => {
+                GreedyDistance::greedily_choose(all_results, tos)
+            }
+            Assignment::Optimal => {
+                OptimalAssignment::optimally_choose(all_results, tos)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::search::create_search;
+    use crate::utilities::*;
+    use crate::world_step::AvoidWaterFilter;
+
+    const MAP: &str = "%-a----*---a--*-%
+                       --*--a----%%-----
+                       -----%%---*---a--
+                       -a--*------a---*-";
+
+    #[test]
+    fn same_results_as_sequential_search() {
+        let world_step = &AvoidWaterFilter::new_from_line_map(MAP);
+        let froms = positions_of('a', MAP);
+        let capacities: HashMap<Position, usize> =
+            positions_of('*', MAP)
+                .into_iter()
+                .map(|p| (p, 2))
+                .collect();
+
+        let sequential = search_each_target(
+            world_step,
+            &froms,
+            &capacities,
+            3,
+            30,
+            &Deadline::never(),
+        );
+
+        for threads in 1..=5 {
+            let parallel = ParallelMultiSearch {
+                assignment: Assignment::Greedy,
+                threads,
+                search: create_search(),
+            }
+            .search_each_target(
+                world_step,
+                &froms,
+                &capacities,
+                3,
+                30,
+                &Deadline::never(),
+            );
+            assert_eq![parallel, sequential];
+        }
+    }
+
+    #[test]
+    fn as_good_assignment_as_sequential_multisearch() {
+        let world_step = &AvoidWaterFilter::new_from_line_map(MAP);
+        let summary = |multisearch: &dyn MultiSearch| {
+            let results = multisearch.search_all(
+                world_step,
+                &positions_of('a', MAP),
+                &positions_of('*', MAP),
+                3,
+                30,
+                &Deadline::never(),
+            );
+            let total: usize =
+                results.iter().map(|r| r.order_length()).sum();
+            (results.len(), total)
+        };
+
+        assert_eq![
+            summary(&ParallelMultiSearch {
+                assignment: Assignment::Greedy,
+                threads: 3,
+                search: create_search(),
+            }),
+            summary(&GreedyDistance {})
+        ];
+        assert_eq![
+            summary(&ParallelMultiSearch {
+                assignment: Assignment::Optimal,
+                threads: 3,
+                search: create_search(),
+            }),
+            summary(&OptimalAssignment {})
+        ];
+    }
+}
@@ -0,0 +1,471 @@
+use crate::strategy::search::SearchResult;
+use crate::strategy::*;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Counts of path cache usage.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CacheStats {
+    /// Paths reused from earlier turns.
+    pub hits: usize,
+    /// Paths searched anew.
+    pub misses: usize,
+    /// Cached paths dropped, being blocked by water or ants.
+    pub blocked: usize,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+impl std::ops::Add for CacheStats {
+    type Output = CacheStats;
+
+    fn add(self, other: CacheStats) -> CacheStats {
+        CacheStats {
+            hits: self.hits + other.hits,
+            misses: self.misses + other.misses,
+            blocked: self.blocked + other.blocked,
+        }
+    }
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "hits {}, misses {}, blocked {}, hit rate {:.1}%",
+            self.hits,
+            self.misses,
+            self.blocked,
+            100.0 * self.hit_rate()
+        )
+    }
+}
+
+/// Paths of ants to their targets, kept between turns and keyed by
+/// the tracked id of the ant and its target. An ant that has moved
+/// along its path, or stayed at its start, reuses the rest of the
+/// path as long as it is not blocked, instead of searching again.
+/// The path of an ant is dropped when the ant is gone or given a
+/// mission to another target.
+#[derive(Default)]
+pub struct PathCache {
+    paths: HashMap<(AntId, Position), SearchResult>,
+    stats: CacheStats,
+}
+
+/// Whether each step of the path is still available from the step
+/// before, as when searching, and not held by one of my ants staying
+/// where it is this turn.
+fn is_open(
+    world_step: &dyn WorldStep,
+    path: &SearchResult,
+    held: &HashSet<Position>,
+) -> bool {
+    let size = world_step.size();
+    path.steps().windows(2).all(|step| {
+        !held.contains(&step[1])
+            && world_step
+                .available_directions(&step[0])
+                .into_iter()
+                .any(|dir| {
+                    step[0].order(dir).target_pos(size) == step[1]
+                })
+    })
+}
+
+impl PathCache {
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Remember path of given ant, e.g. the planned path of a
+    /// mission, to be reused if still open. Replaces the path the ant
+    /// had before.
+    pub fn remember(&mut self, id: AntId, path: SearchResult) {
+        self.paths.retain(|(ant, _), _| *ant != id);
+        self.paths.insert((id, path.last_step()), path);
+    }
+
+    /// Open cached paths from given ants to given targets, shortest
+    /// first. Other cached paths are dropped.
+    fn lookup(
+        &mut self,
+        world_step: &dyn WorldStep,
+        ants: &HashSet<Position>,
+        targets: &HashMap<Position, usize>,
+        tracker: &AntTracker,
+    ) -> Vec<SearchResult> {
+        let positions: HashMap<AntId, &Position> = ants
+            .iter()
+            .flat_map(|ant| tracker.id(ant).map(|id| (id, ant)))
+            .collect();
+        let ordered: HashSet<Position> = world_step
+            .get_orders()
+            .into_iter()
+            .map(|order| order.pos)
+            .collect();
+        let held: HashSet<Position> = world_step
+            .all_my_ants()
+            .into_iter()
+            .filter(|ant| {
+                !ants.contains(ant) && !ordered.contains(ant)
+            })
+            .collect();
+        let mut found = vec![];
+
+        for ((id, target), path) in self.paths.drain() {
+            let ant = match positions.get(&id) {
+                Some(ant) => *ant,
+                None => continue,
+            };
+            let mission_target =
+                tracker.mission(ant).map(|m| &m.target);
+            if mission_target.is_some_and(|t| *t != target)
+                || !targets.contains_key(&target)
+            {
+                continue;
+            }
+
+            let path = if path.first_step() == *ant {
+                path
+            } else if path.order_length() > 0
+                && path.without_first_step().first_step() == *ant
+            {
+                path.without_first_step()
+            } else {
+                continue;
+            };
+            if path.order_length() == 0 {
+                continue;
+            }
+
+            if is_open(world_step, &path, &held) {
+                found.push(path);
+            } else {
+                self.stats.blocked += 1;
+            }
+        }
+
+        found.sort_by_key(|p| (p.order_length(), p.first_step()));
+        found
+    }
+
+    /// Cached paths of available tracked ants, up to the capacity of
+    /// their targets, followed by the paths found by 'search' for the
+    /// remaining capacities, e.g. best_paths_to_target_with_capacity
+    /// or best_paths_along_field. Paths of tracked ants are kept for
+    /// the next turn.
+    pub fn best_paths<F>(
+        &mut self,
+        targets: &HashMap<Position, usize>,
+        world_step: &dyn WorldStep,
+        ants_available: &mut HashSet<Position>,
+        tracker: &AntTracker,
+        search: F,
+    ) -> Vec<SearchResult>
+    where
+        F: FnOnce(
+            &HashMap<Position, usize>,
+            &mut HashSet<Position>,
+        ) -> Vec<SearchResult>,
+    {
+        let mut capacities = targets.clone();
+        let mut results = vec![];
+
+        for path in
+            self.lookup(world_step, ants_available, targets, tracker)
+        {
+            let ant = path.first_step();
+            let left =
+                capacities.entry(path.last_step()).or_insert(0);
+
+            if *left > 0 && ants_available.remove(&ant) {
+                *left -= 1;
+                results.push(path);
+            }
+        }
+        capacities.retain(|_, &mut left| left > 0);
+
+        let searched = search(&capacities, ants_available);
+
+        self.stats.hits += results.len();
+        self.stats.misses += searched.len();
+        results.extend(searched);
+        for path in &results {
+            if let Some(id) = tracker.id(&path.first_step()) {
+                self.remember(id, path.clone());
+            }
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world_step::*;
+
+    fn targets(positions: &[Position]) -> HashMap<Position, usize> {
+        positions.iter().map(|p| (p.clone(), 1)).collect()
+    }
+
+    /// Orders of one turn, with all my ants available and tracked.
+    fn orders(
+        cache: &mut PathCache,
+        tracker: &mut AntTracker,
+        world_step: &dyn WorldStep,
+        target: Position,
+    ) -> Orders {
+        tracker.update(&world_step.all_my_ants());
+        let mut ants: HashSet<Position> =
+            world_step.all_my_ants().into_iter().collect();
+
+        let orders: Orders = cache
+            .best_paths(
+                &targets(&[target]),
+                world_step,
+                &mut ants,
+                tracker,
+                |capacities, ants| {
+                    best_paths_to_target_with_capacity(
+                        capacities,
+                        world_step,
+                        ants,
+                        1,
+                        20,
+                        &Deadline::never(),
+                    )
+                },
+            )
+            .iter()
+            .flat_map(|r| r.first_order(world_step.size()))
+            .collect();
+        tracker.record_orders(&orders, world_step.size());
+        orders
+    }
+
+    #[test]
+    fn reuse_path_of_ant_moved_along_it() {
+        let mut cache = PathCache::default();
+        let mut tracker = AntTracker::default();
+
+        let first = orders(
+            &mut cache,
+            &mut tracker,
+            &BasicWorldStep::new_from_line_map(
+                "----------
+                 -a--*-----
+                 ----------",
+            ),
+            pos(1, 4),
+        );
+        let second = orders(
+            &mut cache,
+            &mut tracker,
+            &BasicWorldStep::new_from_line_map(
+                "----------
+                 --a-*-----
+                 ----------",
+            ),
+            pos(1, 4),
+        );
+
+        assert_eq![first, vec![pos(1, 1).east()]];
+        assert_eq![second, vec![pos(1, 2).east()]];
+        assert_eq![
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                blocked: 0
+            }
+        ];
+    }
+
+    #[test]
+    fn never_reuse_path_of_other_ant() {
+        let mut cache = PathCache::default();
+        let mut tracker = AntTracker::default();
+
+        orders(
+            &mut cache,
+            &mut tracker,
+            &BasicWorldStep::new_from_line_map(
+                "----------
+                 -a--*-----
+                 ----------",
+            ),
+            pos(1, 4),
+        );
+        // The ant ordered east is gone, and a new ant stands on the
+        // second step of its path.
+        tracker.record_orders(&[], &pos(3, 10));
+        tracker.update(&[]);
+        let second = orders(
+            &mut cache,
+            &mut tracker,
+            &BasicWorldStep::new_from_line_map(
+                "----------
+                 --a-*-----
+                 ----------",
+            ),
+            pos(1, 4),
+        );
+
+        assert_eq![second, vec![pos(1, 2).east()]];
+        assert_eq![cache.stats().hits, 0];
+        assert_eq![cache.stats().misses, 2];
+    }
+
+    #[test]
+    fn forget_path_when_mission_changes() {
+        let mut cache = PathCache::default();
+        let mut tracker = AntTracker::default();
+        let world_step = &BasicWorldStep::new_from_line_map(
+            "----------
+             -a--*----*
+             ----------",
+        );
+
+        orders(&mut cache, &mut tracker, world_step, pos(1, 4));
+        tracker.update(&[pos(1, 2)]);
+        tracker.set_mission(Mission::new(
+            "test",
+            SearchResult::start(pos(1, 2)).add_step(pos(1, 1)),
+        ));
+        let mut ants = set![pos(1, 2)];
+        cache.best_paths(
+            &targets(&[pos(1, 4)]),
+            world_step,
+            &mut ants,
+            &tracker,
+            |_, _| vec![],
+        );
+
+        assert_eq![cache.stats().hits, 0];
+        assert_eq![ants, set![pos(1, 2)]];
+    }
+
+    #[test]
+    fn search_again_when_path_is_blocked() {
+        let mut cache = PathCache::default();
+        let mut tracker = AntTracker::default();
+
+        orders(
+            &mut cache,
+            &mut tracker,
+            &AvoidWaterFilter::new_from_line_map(
+                "----------
+                 -a--*-----
+                 ----------",
+            ),
+            pos(1, 4),
+        );
+        let second = orders(
+            &mut cache,
+            &mut tracker,
+            &AvoidWaterFilter::new_from_line_map(
+                "----------
+                 --a%*-----
+                 ----------",
+            ),
+            pos(1, 4),
+        );
+
+        assert_eq![second.len(), 1];
+        assert_ne![second, vec![pos(1, 2).east()]];
+        assert_eq![
+            cache.stats(),
+            CacheStats {
+                hits: 0,
+                misses: 2,
+                blocked: 1
+            }
+        ];
+    }
+
+    #[test]
+    fn search_again_when_later_step_is_taken() {
+        let mut cache = PathCache::default();
+        let mut tracker = AntTracker::default();
+
+        orders(
+            &mut cache,
+            &mut tracker,
+            &BasicWorldStep::new_from_line_map(
+                "-a----*---
+                 ----------",
+            ),
+            pos(0, 6),
+        );
+        // Another ant is ordered onto the fourth step of the path.
+        let world_step = &mut AntCrashFilter::new_from_line_map(
+            "--a-------
+             ----a-----",
+        );
+        world_step.add_order(pos(1, 4).north());
+        tracker.update(&world_step.all_my_ants());
+        let mut ants = set![pos(0, 2)];
+        cache.best_paths(
+            &targets(&[pos(0, 6)]),
+            world_step,
+            &mut ants,
+            &tracker,
+            |_, _| vec![],
+        );
+
+        assert_eq![cache.stats().blocked, 1];
+        assert_eq![cache.stats().hits, 0];
+    }
+
+    #[test]
+    fn forget_paths_to_targets_gone() {
+        let mut cache = PathCache::default();
+        let mut tracker = AntTracker::default();
+
+        orders(
+            &mut cache,
+            &mut tracker,
+            &BasicWorldStep::new_from_line_map("-a-----*--"),
+            pos(0, 7),
+        );
+        orders(
+            &mut cache,
+            &mut tracker,
+            &BasicWorldStep::new_from_line_map("--a--*----"),
+            pos(0, 5),
+        );
+
+        assert_eq![cache.stats().hits, 0];
+        assert_eq![cache.stats().misses, 2];
+    }
+
+    #[test]
+    fn replace_path_of_ant() {
+        let mut cache = PathCache::default();
+        let ant = pos(0, 1);
+
+        cache.remember(
+            1,
+            SearchResult::start(ant.clone()).add_step(pos(0, 2)),
+        );
+        cache.remember(
+            1,
+            SearchResult::start(ant).add_step(pos(0, 0)),
+        );
+
+        assert_eq![cache.paths.len(), 1];
+        assert!(cache.paths.contains_key(&(1, pos(0, 0))));
+    }
+}
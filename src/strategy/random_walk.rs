@@ -1,3 +1,4 @@
+use crate::deadline::Deadline;
 use crate::strategy::Strategy;
 use crate::world_step::WorldStep;
 use ants_ai_challenge_api::Direction;
@@ -22,9 +23,10 @@ fn random_direction(dirs: &[Direction]) -> Option<Direction> {
 
 impl Strategy for RandomWalk {
     fn apply(
-        &self,
+        &mut self,
         world_step: &dyn WorldStep,
         ants: &mut HashSet<Position>,
+        _deadline: &Deadline,
     ) -> Orders {
         ants.iter()
             .flat_map(|ant| {
@@ -51,11 +53,11 @@ mod tests {
              %%%",
         );
 
-        let strategy = RandomWalk {};
+        let mut strategy = RandomWalk {};
         let mut ants = HashSet::from_iter(
             world_step.all_my_ants().iter().cloned(),
         );
 
-        strategy.apply(&world_step, &mut ants);
+        strategy.apply(&world_step, &mut ants, &Deadline::never());
     }
 }
@@ -1,13 +1,20 @@
 use crate::strategy::search::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::collections::HashMap;
 
-/// Breadth first search
+/// Breadth first search, expanding the cheapest positions first by
+/// the step costs of the world, like Dijkstra's algorithm. With the
+/// default step cost of 1 it is a plain breadth first search.
 pub struct BFS {}
 
+/// Number of expanded positions between checks of the deadline.
+const DEADLINE_CHECK_INTERVAL: usize = 64;
+
 impl Search for BFS {
-    /// Nearest orders to 'to' from 'from', is actually a reversed breadth first
-    /// search starting with 'to' and searching for the first maching positions
-    /// in 'from'.
+    /// Cheapest orders to 'to' from 'from', is actually a reversed
+    /// search starting with 'to' and searching for the first matching
+    /// positions in 'from'.
     fn search(
         &self,
         world: &dyn WorldStep,
@@ -15,67 +22,107 @@ impl Search for BFS {
         to: Position,
         max_result_len: usize,
         cutoff_len: usize,
+        deadline: &Deadline,
     ) -> Vec<SearchResult> {
-        // Keep track current positions to search from
-        let mut fringe: HashMap<Position, SearchResult> =
-            HashMap::new();
-
-        // Keep track of the old fringe, since we want to avoid
-        // searching in the wrong direction.
-        let mut old_fringe: HashMap<Position, SearchResult> =
+        // Cheapest known path from 'to' to each reached position,
+        // with its cost.
+        let mut reached: HashMap<Position, (usize, SearchResult)> =
             HashMap::new();
 
-        // Keep track of positions to use in the next iteration.
-        let mut next_fringe: HashMap<Position, SearchResult> =
-            HashMap::new();
+        // Reached positions by cost, cheapest first.
+        let mut queue = BinaryHeap::new();
         let mut results: Vec<SearchResult> = vec![];
 
-        // Add end position to fringe, since we start the search from there.
-        fringe.insert(to.clone(), SearchResult::start(to));
-
-        let mut search_len = 0;
-
-        'search: while !fringe.is_empty()
-            && results.len() < max_result_len
-            && results.len() < from.len()
-            && search_len < cutoff_len
-        {
-            search_len += 1;
-            for (pos, sr) in &fringe {
-                let next_fringe_pos: Vec<Position> = world
-                    .available_directions(&pos)
-                    .iter()
-                    .map(|dir| pos.order(*dir))
-                    .map(|order| order.target_pos(world.size()))
-                    .filter(|target| {
-                        !old_fringe.contains_key(&target)
-                            && !fringe.contains_key(&target)
-                    })
-                    .collect();
-
-                for next_pos in next_fringe_pos {
-                    let next_sr = sr.add_step(next_pos.clone());
-
-                    if from.contains(&next_pos) {
-                        // Add reversed search result
-                        // since we are searching backwards.
-                        results.push(next_sr.reverse());
-
-                        if results.len() >= max_result_len
-                            || results.len() >= from.len()
-                        {
-                            // No nead to search more if we do not
-                            // need any more results
-                            break 'search;
-                        }
-                    }
-                    next_fringe.insert(next_pos, next_sr);
+        if deadline.is_passed() {
+            return results;
+        }
+
+        // Start the search from the end position, searching backwards.
+        reached
+            .insert(to.clone(), (0, SearchResult::start(to.clone())));
+        queue.push(Reverse((0, to.clone())));
+
+        let mut iterations: usize = 0;
+
+        while let Some(Reverse((cost, pos))) = queue.pop() {
+            // Checking time is not free, so only check now and then.
+            iterations += 1;
+            if iterations % DEADLINE_CHECK_INTERVAL == 0
+                && deadline.is_passed()
+            {
+                break;
+            }
+
+            let sr = match reached.get(&pos) {
+                // Skip positions since reached in a cheaper way.
+                Some((best, _)) if *best < cost => continue,
+                Some((_, sr)) => sr.clone(),
+                None => continue,
+            };
+
+            if pos != to && from.contains(&pos) {
+                // Add reversed search result
+                // since we are searching backwards.
+                results.push(sr.reverse());
+
+                if results.len() >= max_result_len
+                    || results.len() >= from.len()
+                {
+                    // No nead to search more if we do not
+                    // need any more results
+                    break;
+                }
+            }
+
+            // Searching backwards, the cost is the cost of moving onto
+            // this position, from the next one.
+            let next_cost = cost + world.step_cost(&pos);
+            if next_cost > cutoff_len {
+                continue;
+            }
+            for dir in world.available_directions(&pos) {
+                let next_pos =
+                    pos.order(dir).target_pos(world.size());
+                let is_cheaper = match reached.get(&next_pos) {
+                    Some((best, _)) => next_cost < *best,
+                    None => true,
+                };
+                if is_cheaper {
+                    reached.insert(
+                        next_pos.clone(),
+                        (next_cost, sr.add_step(next_pos.clone())),
+                    );
+                    queue.push(Reverse((next_cost, next_pos)));
                 }
             }
-            old_fringe = fringe;
-            fringe = next_fringe;
-            next_fringe = HashMap::new();
         }
         results
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world_step::AvoidWaterFilter;
+
+    #[test]
+    fn nearest_first() {
+        let world = &AvoidWaterFilter::new_from_line_map(
+            "-a-b--a
+             -------",
+        );
+
+        let actual = BFS {}.search(
+            world,
+            &set![pos(0, 1), pos(0, 6)],
+            pos(0, 3),
+            10,
+            10,
+            &Deadline::never(),
+        );
+
+        let first_steps: Vec<Position> =
+            actual.iter().map(|r| r.first_step()).collect();
+        assert_eq!(first_steps, vec![pos(0, 1), pos(0, 6)]);
+    }
+}
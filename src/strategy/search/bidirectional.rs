@@ -0,0 +1,313 @@
+use super::manhattan_filter::manhattan;
+use crate::strategy::search::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+
+/// Bidirectional A-star search, searching forwards from the origin
+/// and backwards from the target at the same time until the searches
+/// meet in the middle. The backward search follows the arriving
+/// directions of the world step, so that the found path respects the
+/// available directions of each step also when they are not
+/// symmetric.
+///
+/// Each side is guided by the Manhattan distance to the far end, and
+/// the search stops when neither side can find a path shorter than
+/// the best one where they met.
+pub struct BidirectionalSearch {}
+
+/// Number of expanded positions between checks of the deadline.
+const DEADLINE_CHECK_INTERVAL: usize = 64;
+
+/// One direction of the search, reached positions with their
+/// distance from the start of this side and the previous position.
+struct Side {
+    /// Queued positions by total cost, then Manhattan distance left.
+    queue: BinaryHeap<Reverse<(usize, usize, Position)>>,
+    reached: HashMap<Position, (usize, Option<Position>)>,
+    /// Start of the other side, aimed for.
+    goal: Position,
+    backwards: bool,
+}
+
+impl Side {
+    fn new(
+        start: &Position,
+        goal: &Position,
+        backwards: bool,
+    ) -> Side {
+        let mut side = Side {
+            queue: BinaryHeap::new(),
+            reached: HashMap::new(),
+            goal: goal.clone(),
+            backwards,
+        };
+        side.reached.insert(start.clone(), (0, None));
+        side.queue.push(Reverse((0, 0, start.clone())));
+        side
+    }
+
+    /// Lowest total cost in queue.
+    fn min_cost(&self) -> Option<usize> {
+        self.queue.peek().map(|Reverse((f, _, _))| *f)
+    }
+
+    fn distance(&self, pos: &Position) -> Option<usize> {
+        self.reached.get(pos).map(|(g, _)| *g)
+    }
+
+    /// Positions from given position back to the start of this side.
+    fn steps_back(&self, pos: &Position) -> Vec<Position> {
+        let mut steps = vec![pos.clone()];
+        while let Some((_, Some(previous))) =
+            self.reached.get(steps.last().expect("a step"))
+        {
+            steps.push(previous.clone());
+        }
+        steps
+    }
+
+    /// Expand the nearest queued position, giving each newly reached
+    /// position with its distance.
+    fn expand(
+        &mut self,
+        world: &dyn WorldStep,
+        cutoff_len: usize,
+    ) -> Vec<(Position, usize)> {
+        let size = world.size();
+        let Reverse((_, _, pos)) = match self.queue.pop() {
+            Some(next) => next,
+            None => return vec![],
+        };
+        let g = self
+            .distance(&pos)
+            .expect("queued positions are reached");
+
+        let directions = if self.backwards {
+            world.arriving_directions(&pos)
+        } else {
+            world.available_directions(&pos)
+        };
+
+        let mut reached = vec![];
+        for dir in directions {
+            let next = pos.order(dir).target_pos(size);
+            let next_g = g + 1;
+            let h = manhattan(&next, &self.goal, size) as usize;
+
+            let is_better = match self.distance(&next) {
+                Some(old) => next_g < old,
+                None => true,
+            };
+            if is_better && next_g + h <= cutoff_len {
+                self.reached.insert(
+                    next.clone(),
+                    (next_g, Some(pos.clone())),
+                );
+                self.queue.push(Reverse((
+                    next_g + h,
+                    h,
+                    next.clone(),
+                )));
+                reached.push((next, next_g));
+            }
+        }
+        reached
+    }
+}
+
+impl BidirectionalSearch {
+    /// Shortest path from 'from' to 'to' no longer than the cutoff.
+    fn single_search(
+        &self,
+        world: &dyn WorldStep,
+        from: &Position,
+        to: &Position,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> Option<SearchResult> {
+        let mut forward = Side::new(from, to, false);
+        let mut backward = Side::new(to, from, true);
+
+        // Shortest length found where the sides met, and where.
+        let mut best: Option<(usize, Position)> = None;
+        let mut iterations: usize = 0;
+
+        loop {
+            // Checking time is not free, so only check now and then.
+            iterations += 1;
+            if iterations % DEADLINE_CHECK_INTERVAL == 0
+                && deadline.is_passed()
+            {
+                return None;
+            }
+
+            let (forward_cost, backward_cost) =
+                match (forward.min_cost(), backward.min_cost()) {
+                    (Some(f), Some(b)) => (f, b),
+                    _ => break,
+                };
+
+            // No path through the queued positions is shorter.
+            if let Some((length, _)) = &best {
+                if std::cmp::max(forward_cost, backward_cost)
+                    >= *length
+                {
+                    break;
+                }
+            }
+
+            let (side, other) = if forward_cost <= backward_cost {
+                (&mut forward, &backward)
+            } else {
+                (&mut backward, &forward)
+            };
+
+            for (pos, g) in side.expand(world, cutoff_len) {
+                if let Some(other_g) = other.distance(&pos) {
+                    let length = g + other_g;
+                    let is_better = match &best {
+                        Some((best_length, best_pos)) => {
+                            (length, &pos) < (*best_length, best_pos)
+                        }
+                        None => true,
+                    };
+                    if length <= cutoff_len && is_better {
+                        best = Some((length, pos));
+                    }
+                }
+            }
+        }
+
+        best.map(|(_, meeting)| {
+            let mut steps = forward.steps_back(&meeting);
+            steps.reverse();
+            steps.extend(
+                backward.steps_back(&meeting).into_iter().skip(1),
+            );
+
+            steps[1..].iter().fold(
+                SearchResult::start(steps[0].clone()),
+                |r, s| r.add_step(s.clone()),
+            )
+        })
+    }
+}
+
+impl Search for BidirectionalSearch {
+    fn search(
+        &self,
+        world: &dyn WorldStep,
+        from: &HashSet<Position>,
+        to: Position,
+        max_result_len: usize,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> Vec<SearchResult> {
+        search_each_origin(
+            world,
+            from,
+            &to,
+            max_result_len,
+            cutoff_len,
+            deadline,
+            |f, _, max_cost| {
+                self.single_search(world, f, &to, max_cost, deadline)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world_step::{AvoidWaterFilter, BasicWorldStep, Tile};
+
+    #[test]
+    fn meet_in_the_middle() {
+        let world = &AvoidWaterFilter::new_from_line_map(
+            "%%%%%%%%
+             %------%
+             %%%%%%%%",
+        );
+
+        let actual = BidirectionalSearch {}.single_search(
+            world,
+            &pos(1, 1),
+            &pos(1, 6),
+            10,
+            &Deadline::never(),
+        );
+
+        let expected = (2..=6)
+            .fold(SearchResult::start(pos(1, 1)), |r, col| {
+                r.add_step(pos(1, col))
+            });
+        assert_eq!(actual, Some(expected));
+    }
+
+    /// Directions given to an ant, without a way back.
+    struct OneWay {
+        inner: AvoidWaterFilter<BasicWorldStep>,
+    }
+
+    impl WorldStep for OneWay {
+        fn add_order(&mut self, order: Order) -> &mut dyn WorldStep {
+            self.inner.add_order(order);
+            self
+        }
+        fn get_orders(&self) -> Orders {
+            self.inner.get_orders()
+        }
+        fn size(&self) -> &Position {
+            self.inner.size()
+        }
+        fn all_my_ants(&self) -> Vec<Position> {
+            self.inner.all_my_ants()
+        }
+        fn available_directions(
+            &self,
+            pos: &Position,
+        ) -> Vec<Direction> {
+            // Only eastwards on the middle row.
+            let dirs = self.inner.available_directions(pos);
+            if pos.row == 1 {
+                dirs.into_iter().filter(|d| *d != West).collect()
+            } else {
+                dirs
+            }
+        }
+        fn tile(&self, pos: &Position) -> Tile {
+            self.inner.tile(pos)
+        }
+        fn get_positions(&self, tile: Tile) -> Vec<Position> {
+            self.inner.get_positions(tile)
+        }
+        fn is_visible(&self, pos: &Position) -> bool {
+            self.inner.is_visible(pos)
+        }
+    }
+
+    #[test]
+    fn respect_directions_on_both_sides() {
+        let world = &OneWay {
+            inner: AvoidWaterFilter::new_from_line_map(
+                "%%%%%%%
+                 %-----%
+                 %-----%
+                 %%%%%%%",
+            ),
+        };
+
+        let actual = BidirectionalSearch {}.single_search(
+            world,
+            &pos(1, 5),
+            &pos(1, 1),
+            20,
+            &Deadline::never(),
+        );
+
+        // Not straight west, but around through the row below.
+        assert_eq!(actual.map(|r| r.order_length()), Some(6));
+    }
+}
@@ -0,0 +1,330 @@
+use crate::strategy::search::*;
+use crate::world_step::{Tile, TileKind};
+use std::collections::HashMap;
+
+/// Distances to the nearest of a set of sources, for all positions
+/// reachable within the cutoff length. The field is flooded once,
+/// e.g. from all food at the start of a turn, after which the
+/// distance and the direction towards the nearest source can be
+/// read in constant time for each ant.
+pub struct DistanceField {
+    sources: HashSet<Position>,
+    distances: HashMap<Position, usize>,
+}
+
+impl DistanceField {
+    /// Flood fill from all sources, until either the cutoff length
+    /// or the deadline is reached.
+    pub fn flood(
+        world: &dyn WorldStep,
+        sources: &HashSet<Position>,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> DistanceField {
+        DistanceField::flood_until(
+            world,
+            sources,
+            cutoff_len,
+            deadline,
+            |_| false,
+        )
+    }
+
+    /// Flood fill from all sources, until either the cutoff length
+    /// or the deadline is reached, or 'is_done' is true after a
+    /// completed distance level. 'is_done' is given each newly
+    /// reached position.
+    fn flood_until<F: FnMut(&Position) -> bool>(
+        world: &dyn WorldStep,
+        sources: &HashSet<Position>,
+        cutoff_len: usize,
+        deadline: &Deadline,
+        mut is_done: F,
+    ) -> DistanceField {
+        let mut distances: HashMap<Position, usize> =
+            sources.iter().map(|s| (s.clone(), 0)).collect();
+        let mut fringe: Vec<Position> =
+            sources.iter().cloned().collect();
+        let mut distance = 0;
+
+        while !fringe.is_empty()
+            && distance < cutoff_len
+            && !deadline.is_passed()
+        {
+            distance += 1;
+            let mut next_fringe = vec![];
+            let mut done = false;
+
+            for pos in fringe.iter() {
+                for dir in world.available_directions(pos) {
+                    let next =
+                        pos.order(dir).target_pos(world.size());
+                    if !distances.contains_key(&next) {
+                        done |= is_done(&next);
+                        distances.insert(next.clone(), distance);
+                        next_fringe.push(next);
+                    }
+                }
+            }
+
+            if done {
+                break;
+            }
+            fringe = next_fringe;
+        }
+
+        DistanceField {
+            sources: sources.clone(),
+            distances,
+        }
+    }
+
+    /// Positions the field is flooded from.
+    pub fn sources(&self) -> &HashSet<Position> {
+        &self.sources
+    }
+
+    /// Distance to nearest source, or None if not reached by flood.
+    pub fn distance(&self, pos: &Position) -> Option<usize> {
+        self.distances.get(pos).cloned()
+    }
+
+    /// Direction taking one step closer to the nearest source, or
+    /// None if already at a source or not reached by the flood.
+    pub fn gradient(
+        &self,
+        world: &dyn WorldStep,
+        pos: &Position,
+    ) -> Option<Direction> {
+        let distance = self.distance(pos)?;
+        if distance == 0 {
+            return None;
+        }
+
+        world.available_directions(pos).into_iter().find(|&dir| {
+            let next = pos.order(dir).target_pos(world.size());
+            self.distance(&next) == Some(distance - 1)
+        })
+    }
+
+    /// Path from given position to nearest source, following the
+    /// gradient.
+    pub fn path(
+        &self,
+        world: &dyn WorldStep,
+        from: &Position,
+    ) -> Option<SearchResult> {
+        self.distance(from)?;
+
+        let mut current = from.clone();
+        let mut result = SearchResult::start(current.clone());
+
+        while let Some(dir) = self.gradient(world, &current) {
+            current = current.order(dir).target_pos(world.size());
+            result = result.add_step(current.clone());
+        }
+        Some(result)
+    }
+}
+
+/// Distance fields flooded once per turn from the targets of the
+/// strategies, shared by all strategies planning the turn instead of
+/// each searching anew, see `FieldLayer`.
+pub struct DistanceFields {
+    pub food: DistanceField,
+    pub enemy_hills: DistanceField,
+    /// Flooded from all positions never seen.
+    pub unexplored: DistanceField,
+}
+
+impl DistanceFields {
+    /// Flood all fields, until either the cutoff length or the
+    /// deadline is reached.
+    pub fn flood(
+        world: &dyn WorldStep,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> DistanceFields {
+        let size = world.size();
+        let mut unexplored = HashSet::new();
+        for row in 0..size.row {
+            for col in 0..size.col {
+                let p = pos(row, col);
+                if world.tile(&p) == Tile::Unknown {
+                    unexplored.insert(p);
+                }
+            }
+        }
+
+        let flood = |sources: &HashSet<Position>| {
+            DistanceField::flood(world, sources, cutoff_len, deadline)
+        };
+
+        DistanceFields {
+            food: flood(
+                &world
+                    .get_positions(Tile::Food)
+                    .into_iter()
+                    .collect(),
+            ),
+            enemy_hills: flood(
+                &world
+                    .get_enemy_positions(TileKind::Hill)
+                    .into_iter()
+                    .collect(),
+            ),
+            unexplored: flood(&unexplored),
+        }
+    }
+}
+
+/// Search using a distance field flooded from the target, stopping
+/// when enough origins are reached.
+pub struct DistanceFieldSearch {}
+
+impl Search for DistanceFieldSearch {
+    fn search(
+        &self,
+        world: &dyn WorldStep,
+        from: &HashSet<Position>,
+        to: Position,
+        max_result_len: usize,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> Vec<SearchResult> {
+        let wanted = std::cmp::min(max_result_len, from.len());
+        let mut reached = 0;
+
+        let field = DistanceField::flood_until(
+            world,
+            &[to].iter().cloned().collect(),
+            cutoff_len,
+            deadline,
+            |pos| {
+                if from.contains(pos) {
+                    reached += 1;
+                }
+                reached >= wanted
+            },
+        );
+
+        let mut results: Vec<SearchResult> = from
+            .iter()
+            .flat_map(|f| field.path(world, f))
+            .filter(|r| r.order_length() > 0)
+            .collect();
+
+        results.sort_by_key(|r| (r.order_length(), r.first_step()));
+        results.truncate(max_result_len);
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::*;
+    use crate::world_step::{AvoidWaterFilter, VisibilityLayer};
+
+    #[test]
+    fn distances_around_water() {
+        let map = "*-%-
+                   --%-
+                   ----";
+        let world = &AvoidWaterFilter::new_from_line_map(map);
+
+        let field = DistanceField::flood(
+            world,
+            &positions_of('*', map),
+            10,
+            &Deadline::never(),
+        );
+
+        assert_eq!(Some(0), field.distance(&pos(0, 0)));
+        assert_eq!(Some(1), field.distance(&pos(0, 1)));
+        assert_eq!(None, field.distance(&pos(0, 2)));
+        // Wrapping around to the left is shorter than going around
+        // the water.
+        assert_eq!(Some(1), field.distance(&pos(0, 3)));
+        assert_eq!(Some(2), field.distance(&pos(1, 3)));
+    }
+
+    #[test]
+    fn gradient_leads_to_nearest_source() {
+        let map = "*-----*-----
+                   --a---------";
+        let world = &AvoidWaterFilter::new_from_line_map(map);
+
+        let field = DistanceField::flood(
+            world,
+            &positions_of('*', map),
+            10,
+            &Deadline::never(),
+        );
+
+        let ant = pos(1, 2);
+        let path = field.path(world, &ant).expect("reachable");
+
+        assert_eq!(3, path.order_length());
+        assert_eq!(pos(0, 0), path.last_step());
+        assert_eq!(None, field.gradient(world, &pos(0, 6)));
+    }
+
+    #[test]
+    fn fields_of_food_hills_and_unexplored_positions() {
+        let map = "?a-*
+                   ??-1";
+        let world = &VisibilityLayer::new_from_line_map(map, 0);
+
+        let fields =
+            DistanceFields::flood(world, 10, &Deadline::never());
+
+        assert_eq!(Some(2), fields.food.distance(&pos(0, 1)));
+        assert_eq!(Some(3), fields.enemy_hills.distance(&pos(0, 1)));
+        assert_eq!(Some(1), fields.unexplored.distance(&pos(0, 1)));
+        assert_eq!(
+            &positions_of('?', map),
+            fields.unexplored.sources()
+        );
+    }
+
+    #[test]
+    fn search_nearest_origins_only() {
+        let map = "%--a-b-a--a---%";
+        let world = &AvoidWaterFilter::new_from_line_map(map);
+
+        let actual = DistanceFieldSearch {}
+            .search(
+                world,
+                &positions_of('a', map),
+                pos(0, 5),
+                2,
+                10,
+                &Deadline::never(),
+            )
+            .iter()
+            .flat_map(|res| res.first_order(world.size()))
+            .collect::<HashSet<Order>>();
+
+        assert_eq!(set![pos(0, 3).east(), pos(0, 7).west()], actual);
+    }
+
+    #[test]
+    fn search_restricted_by_cutoff_length() {
+        let map = "b--a-%";
+        let world = &AvoidWaterFilter::new_from_line_map(map);
+        let search = |cutoff_len| {
+            DistanceFieldSearch {}.search(
+                world,
+                &set![pos(0, 3)],
+                pos(0, 0),
+                1,
+                cutoff_len,
+                &Deadline::never(),
+            )
+        };
+
+        assert_eq!(0, search(2).len());
+        assert_eq!(1, search(3).len());
+    }
+}
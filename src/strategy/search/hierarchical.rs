@@ -0,0 +1,901 @@
+use crate::strategy::search::*;
+use crate::world_step::Tile;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::RwLock;
+
+/// Number of steps of a path searched again in the current world
+/// step, e.g. around ants, the rest of the path only avoiding water.
+const REFINED_STEPS: usize = 10;
+
+/// Cluster, by row and column of clusters.
+type Cluster = (u16, u16);
+
+/// Precomputed path between two positions, both ends included.
+#[derive(Debug, Clone)]
+struct Edge {
+    to: Position,
+    path: Vec<Position>,
+}
+
+impl Edge {
+    fn cost(&self) -> usize {
+        self.path.len() - 1
+    }
+}
+
+/// Shortest paths inside a cluster from one start position, as the
+/// previous step of each reached position, by position in cluster.
+struct ClusterPaths {
+    first: Position,
+    size: usize,
+    previous: Vec<Option<Position>>,
+}
+
+impl ClusterPaths {
+    fn index(&self, p: &Position) -> usize {
+        (p.row - self.first.row) as usize * self.size
+            + (p.col - self.first.col) as usize
+    }
+
+    fn contains(&self, p: &Position) -> bool {
+        let inside = |at: u16, first: u16| {
+            at >= first && ((at - first) as usize) < self.size
+        };
+        inside(p.row, self.first.row) && inside(p.col, self.first.col)
+    }
+
+    /// Path from the start to 'to', if reached.
+    fn path_to(&self, to: &Position) -> Option<Vec<Position>> {
+        if !self.contains(to) {
+            return None;
+        }
+        let mut path = vec![to.clone()];
+        loop {
+            let last = path.last().expect("a step");
+            let before = self.previous[self.index(last)].as_ref()?;
+            if before == last {
+                path.reverse();
+                return Some(path);
+            }
+            path.push(before.clone());
+        }
+    }
+}
+
+/// Abstraction of the torus map into square clusters, with the
+/// entrances between neighbouring clusters as nodes. Entrances of
+/// the same cluster are connected by their shortest path inside the
+/// cluster, and entrances on both sides of a cluster border by a
+/// single step. Only water is taken into account, since it never
+/// moves, letting the graph be kept and only the clusters around new
+/// water be rebuilt.
+pub struct ClusterGraph {
+    size: Position,
+    cluster_size: u16,
+    water: Vec<bool>,
+    entrances: HashMap<Cluster, Vec<Position>>,
+    edges: HashMap<Position, Vec<Edge>>,
+}
+
+impl ClusterGraph {
+    /// Build graph of the water known in given world step.
+    pub fn new(
+        world: &dyn WorldStep,
+        cluster_size: u16,
+    ) -> ClusterGraph {
+        let mut graph =
+            ClusterGraph::without_water(world.size(), cluster_size);
+        graph.add_water(world, &Deadline::never());
+        graph
+    }
+
+    /// Graph of a world of given size without any water.
+    fn without_water(
+        size: &Position,
+        cluster_size: u16,
+    ) -> ClusterGraph {
+        let mut graph = ClusterGraph {
+            size: size.clone(),
+            cluster_size: std::cmp::max(cluster_size, 1),
+            water: vec![false; size.row as usize * size.col as usize],
+            entrances: HashMap::new(),
+            edges: HashMap::new(),
+        };
+        let (rows, cols) = graph.clusters();
+        for cluster_row in 0..rows {
+            for cluster_col in 0..cols {
+                graph.add_borders((cluster_row, cluster_col));
+            }
+        }
+        let clusters: Vec<Cluster> =
+            graph.entrances.keys().cloned().collect();
+        for cluster in clusters {
+            graph.add_cluster_edges(cluster);
+        }
+        graph
+    }
+
+    fn index(&self, p: &Position) -> usize {
+        p.row as usize * self.size.col as usize + p.col as usize
+    }
+
+    fn is_open(&self, p: &Position) -> bool {
+        !self.water[self.index(p)]
+    }
+
+    /// Water of given world step not yet in the graph, by cluster.
+    fn new_water(
+        &self,
+        world: &dyn WorldStep,
+    ) -> HashMap<Cluster, Vec<Position>> {
+        let mut result: HashMap<Cluster, Vec<Position>> =
+            HashMap::new();
+        for p in world.get_positions(Tile::Water) {
+            if p.row < self.size.row
+                && p.col < self.size.col
+                && self.is_open(&p)
+            {
+                result
+                    .entry(self.cluster_of(&p))
+                    .or_default()
+                    .push(p);
+            }
+        }
+        result
+    }
+
+    /// Whether given world step has water not yet in the graph.
+    fn has_new_water(&self, world: &dyn WorldStep) -> bool {
+        world.get_positions(Tile::Water).iter().any(|p| {
+            p.row < self.size.row
+                && p.col < self.size.col
+                && self.is_open(p)
+        })
+    }
+
+    /// Add the water of given world step not yet in the graph,
+    /// rebuilding the clusters around the new water one cluster at a
+    /// time, until the deadline is passed. The graph is complete for
+    /// the water added so far, the rest is added by a later call.
+    pub fn add_water(
+        &mut self,
+        world: &dyn WorldStep,
+        deadline: &Deadline,
+    ) {
+        let mut new_water: Vec<(Cluster, Vec<Position>)> =
+            self.new_water(world).into_iter().collect();
+        new_water.sort();
+
+        for (cluster, waters) in new_water {
+            if deadline.is_passed() {
+                break;
+            }
+            for p in waters {
+                let index = self.index(&p);
+                self.water[index] = true;
+            }
+            self.rebuild_around(cluster);
+        }
+    }
+
+    /// Given cluster and its neighbours, wrapping around.
+    fn around(&self, cluster: Cluster) -> Vec<Cluster> {
+        let (rows, cols) = self.clusters();
+        let (row, col) = cluster;
+        let mut result = vec![
+            cluster,
+            ((row + rows - 1) % rows, col),
+            ((row + 1) % rows, col),
+            (row, (col + cols - 1) % cols),
+            (row, (col + 1) % cols),
+        ];
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    /// Rebuild entrances and edges of the clusters whose borders
+    /// touch the given cluster, after water is added to it.
+    fn rebuild_around(&mut self, cluster: Cluster) {
+        let region = self.around(cluster);
+        for c in region.iter() {
+            for entrance in
+                self.entrances.remove(c).unwrap_or_default()
+            {
+                self.edges.remove(&entrance);
+            }
+        }
+
+        // Borders are added by the cluster to the west or north of
+        // them, also for borders to clusters outside the region,
+        // giving back the entrances removed on that side.
+        let (rows, cols) = self.clusters();
+        let mut bordering: Vec<Cluster> = region
+            .iter()
+            .flat_map(|&(row, col)| {
+                vec![
+                    (row, col),
+                    ((row + rows - 1) % rows, col),
+                    (row, (col + cols - 1) % cols),
+                ]
+            })
+            .collect();
+        bordering.sort();
+        bordering.dedup();
+        for c in bordering {
+            self.add_borders(c);
+        }
+
+        for c in region {
+            self.add_cluster_edges(c);
+        }
+    }
+
+    fn clusters(&self) -> Cluster {
+        let count = |len: u16| {
+            (len as usize).div_ceil(self.cluster_size as usize) as u16
+        };
+        (count(self.size.row), count(self.size.col))
+    }
+
+    fn cluster_of(&self, p: &Position) -> Cluster {
+        (p.row / self.cluster_size, p.col / self.cluster_size)
+    }
+
+    /// Last row or column of given cluster row or column.
+    fn last_of(&self, cluster: u16, len: u16) -> u16 {
+        std::cmp::min((cluster + 1) * self.cluster_size, len) - 1
+    }
+
+    fn add_edge(&mut self, path: Vec<Position>) {
+        let from = path.first().expect("a path").clone();
+        let to = path.last().expect("a path").clone();
+        let edges = self.edges.entry(from).or_default();
+        if !edges.iter().any(|e| e.to == to) {
+            edges.push(Edge { to, path });
+        }
+    }
+
+    fn add_entrance(&mut self, p: &Position) {
+        let entrances =
+            self.entrances.entry(self.cluster_of(p)).or_default();
+        if !entrances.contains(p) {
+            entrances.push(p.clone());
+        }
+    }
+
+    /// Entrances along the border between two neighbouring clusters,
+    /// given as pairs of positions on each side. Each open stretch
+    /// of the border gets an entrance in its middle, or one at each
+    /// end if it is long.
+    fn add_border(&mut self, pairs: Vec<(Position, Position)>) {
+        let mut stretch: Vec<(Position, Position)> = vec![];
+        for pair in pairs.into_iter().map(Some).chain(Some(None)) {
+            match pair {
+                Some((a, b))
+                    if self.is_open(&a) && self.is_open(&b) =>
+                {
+                    stretch.push((a, b));
+                    continue;
+                }
+                _ => {}
+            }
+            let picked = if stretch.len() >= 6 {
+                vec![0, stretch.len() - 1]
+            } else if !stretch.is_empty() {
+                vec![stretch.len() / 2]
+            } else {
+                vec![]
+            };
+            for i in picked {
+                let (a, b) = stretch[i].clone();
+                self.add_entrance(&a);
+                self.add_entrance(&b);
+                self.add_edge(vec![a.clone(), b.clone()]);
+                self.add_edge(vec![b, a]);
+            }
+            stretch.clear();
+        }
+    }
+
+    /// Entrances on the borders to the clusters to the east and to
+    /// the south of given cluster, wrapping around.
+    fn add_borders(&mut self, cluster: Cluster) {
+        let (rows, cols) = self.clusters();
+        let (cluster_row, cluster_col) = cluster;
+        let size = self.size.clone();
+        let first_row = cluster_row * self.cluster_size;
+        let first_col = cluster_col * self.cluster_size;
+        let last_row = self.last_of(cluster_row, size.row);
+        let last_col = self.last_of(cluster_col, size.col);
+
+        if cols > 1 {
+            let east = (last_col + 1) % size.col;
+            self.add_border(
+                (first_row..=last_row)
+                    .map(|r| (pos(r, last_col), pos(r, east)))
+                    .collect(),
+            );
+        }
+        if rows > 1 {
+            let south = (last_row + 1) % size.row;
+            self.add_border(
+                (first_col..=last_col)
+                    .map(|c| (pos(last_row, c), pos(south, c)))
+                    .collect(),
+            );
+        }
+    }
+
+    /// Shortest paths inside the cluster of 'start', from 'start' to
+    /// all positions reached.
+    fn cluster_paths(&self, start: &Position) -> ClusterPaths {
+        let cluster = self.cluster_of(start);
+        let size = self.cluster_size as usize;
+        let mut paths = ClusterPaths {
+            first: pos(
+                cluster.0 * self.cluster_size,
+                cluster.1 * self.cluster_size,
+            ),
+            size,
+            previous: vec![None; size * size],
+        };
+        let mut queue = VecDeque::new();
+        let start_index = paths.index(start);
+        paths.previous[start_index] = Some(start.clone());
+        queue.push_back(start.clone());
+
+        while let Some(p) = queue.pop_front() {
+            for dir in [North, East, South, West].iter() {
+                let next = p.order(*dir).target_pos(&self.size);
+                if self.cluster_of(&next) != cluster
+                    || !self.is_open(&next)
+                {
+                    continue;
+                }
+                let index = paths.index(&next);
+                if paths.previous[index].is_none() {
+                    paths.previous[index] = Some(p.clone());
+                    queue.push_back(next);
+                }
+            }
+        }
+        paths
+    }
+
+    /// Edges between the entrances of given cluster.
+    fn add_cluster_edges(&mut self, cluster: Cluster) {
+        let entrances =
+            self.entrances.get(&cluster).cloned().unwrap_or_default();
+
+        for from in entrances.iter() {
+            let previous = self.cluster_paths(from);
+            for to in entrances.iter().filter(|e| *e != from) {
+                if let Some(path) = previous.path_to(to) {
+                    self.add_edge(path);
+                }
+            }
+        }
+    }
+
+    /// Edges to 'to' from the entrances of its cluster, by entrance.
+    fn edges_to(&self, to: &Position) -> HashMap<Position, Edge> {
+        let paths = self.cluster_paths(to);
+        self.entrances
+            .get(&self.cluster_of(to))
+            .map(|e| e.iter().collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|e| *e != to)
+            .flat_map(|e| paths.path_to(e))
+            .map(|mut path| {
+                path.reverse();
+                let from = path[0].clone();
+                (
+                    from,
+                    Edge {
+                        to: to.clone(),
+                        path,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Shortest distances to 'to' from all entrances within the
+    /// cutoff, through the graph.
+    fn goal_tree(
+        &self,
+        to: &Position,
+        cutoff_len: usize,
+    ) -> GoalTree {
+        let mut tree = GoalTree {
+            to: to.clone(),
+            goal_edges: self.edges_to(to),
+            reached: HashMap::new(),
+        };
+        let mut queue = BinaryHeap::new();
+        if self.edges.contains_key(to) {
+            tree.reached.insert(to.clone(), (0, to.clone()));
+            queue.push(Reverse((0, to.clone())));
+        }
+        for (entrance, edge) in tree.goal_edges.iter() {
+            if edge.cost() <= cutoff_len {
+                tree.reached.insert(
+                    entrance.clone(),
+                    (edge.cost(), to.clone()),
+                );
+                queue.push(Reverse((edge.cost(), entrance.clone())));
+            }
+        }
+
+        // Edges go both ways, so distances from 'to' are distances to
+        // 'to'.
+        while let Some(Reverse((distance, node))) = queue.pop() {
+            if tree.distance(&node).is_some_and(|d| d < distance) {
+                continue;
+            }
+            for edge in self.edges.get(&node).into_iter().flatten() {
+                let next_distance = distance + edge.cost();
+                let is_better = match tree.distance(&edge.to) {
+                    Some(d) => next_distance < d,
+                    None => true,
+                };
+                if next_distance <= cutoff_len && is_better {
+                    tree.reached.insert(
+                        edge.to.clone(),
+                        (next_distance, node.clone()),
+                    );
+                    queue.push(Reverse((
+                        next_distance,
+                        edge.to.clone(),
+                    )));
+                }
+            }
+        }
+        tree
+    }
+
+    /// Steps from a reached node of the tree to its target.
+    fn steps_to_goal(
+        &self,
+        tree: &GoalTree,
+        from: &Position,
+    ) -> Vec<Position> {
+        let mut steps = vec![from.clone()];
+        let mut node = from.clone();
+        while node != tree.to {
+            let next = tree.reached[&node].1.clone();
+            let edge = self
+                .edges
+                .get(&node)
+                .and_then(|edges| edges.iter().find(|e| e.to == next))
+                .unwrap_or_else(|| &tree.goal_edges[&node]);
+            steps.extend(edge.path[1..].iter().cloned());
+            node = next;
+        }
+        steps
+    }
+
+    /// Shortest path from 'from' to the target of the tree, no longer
+    /// than the cutoff, leaving the cluster of 'from' through one of
+    /// its entrances unless the target is in the same cluster.
+    fn path_from(
+        &self,
+        tree: &GoalTree,
+        from: &Position,
+        cutoff_len: usize,
+    ) -> Option<Vec<Position>> {
+        let paths = self.cluster_paths(from);
+        let mut exits: Vec<&Position> = self
+            .entrances
+            .get(&self.cluster_of(from))
+            .map(|e| e.iter().collect())
+            .unwrap_or_default();
+        exits.push(&tree.to);
+
+        let (_, first_steps) = exits
+            .into_iter()
+            .flat_map(|exit| paths.path_to(exit))
+            .flat_map(|path| {
+                let exit = path.last().expect("a path");
+                let rest = if *exit == tree.to {
+                    0
+                } else {
+                    tree.distance(exit)?
+                };
+                Some((path.len() - 1 + rest, path))
+            })
+            .filter(|(length, _)| *length <= cutoff_len)
+            .min_by_key(|(length, _)| *length)?;
+
+        let exit = first_steps.last().expect("a path");
+        let mut steps = first_steps.clone();
+        steps.extend(
+            self.steps_to_goal(tree, exit).into_iter().skip(1),
+        );
+        Some(steps)
+    }
+
+    /// Shortest path from 'from' to 'to' through the graph, avoiding
+    /// water only, no longer than the cutoff.
+    pub fn path(
+        &self,
+        from: &Position,
+        to: &Position,
+        cutoff_len: usize,
+    ) -> Option<Vec<Position>> {
+        self.path_from(
+            &self.goal_tree(to, cutoff_len),
+            from,
+            cutoff_len,
+        )
+    }
+}
+
+/// Distances to a target through a cluster graph, with the next node
+/// on the way, found once and shared by the searches from all
+/// origins.
+struct GoalTree {
+    to: Position,
+    /// Edges to the target from the entrances of its cluster.
+    goal_edges: HashMap<Position, Edge>,
+    reached: HashMap<Position, (usize, Position)>,
+}
+
+impl GoalTree {
+    fn distance(&self, node: &Position) -> Option<usize> {
+        self.reached.get(node).map(|(d, _)| *d)
+    }
+}
+
+/// Hierarchical path planning (HPA*) for long distances on large
+/// maps. Paths are planned through a cluster graph of the map, see
+/// ClusterGraph, kept between searches and updated with new water
+/// by update_graph, e.g. once per turn. Only
+/// the first steps of each path are searched in the current world
+/// step, the rest is only known to avoid water, which is enough to
+/// pick the ants and the first order.
+///
+/// The water is the water of the world step, which for the agent
+/// includes the water remembered from earlier turns. Keep the same
+/// search between turns to reuse the graph, e.g. owned by the
+/// strategy. The graph is shared by searches on several threads.
+pub struct HierarchicalSearch {
+    cluster_size: u16,
+    graph: RwLock<Option<ClusterGraph>>,
+}
+
+impl HierarchicalSearch {
+    pub fn new(cluster_size: u16) -> HierarchicalSearch {
+        HierarchicalSearch {
+            cluster_size,
+            graph: RwLock::new(None),
+        }
+    }
+
+    /// Add new water of given world step to the cluster graph, or
+    /// build the graph if the world has changed size, until the
+    /// deadline is passed, see ClusterGraph::add_water. Searches use
+    /// the graph as is, so update once per turn, before searching.
+    pub fn update_graph(
+        &self,
+        world: &dyn WorldStep,
+        deadline: &Deadline,
+    ) {
+        let is_current = match self
+            .graph
+            .read()
+            .expect("cluster graph lock")
+            .as_ref()
+        {
+            Some(g) => {
+                g.size == *world.size() && !g.has_new_water(world)
+            }
+            None => false,
+        };
+        if is_current {
+            return;
+        }
+
+        let mut graph =
+            self.graph.write().expect("cluster graph lock");
+        match graph.as_mut() {
+            Some(g) if g.size == *world.size() => {
+                g.add_water(world, deadline)
+            }
+            _ => {
+                let mut g = ClusterGraph::without_water(
+                    world.size(),
+                    self.cluster_size,
+                );
+                g.add_water(world, deadline);
+                *graph = Some(g);
+            }
+        }
+    }
+
+    /// Whether there is a graph of a world of given size.
+    fn has_graph(&self, size: &Position) -> bool {
+        self.graph
+            .read()
+            .expect("cluster graph lock")
+            .as_ref()
+            .is_some_and(|g| g.size == *size)
+    }
+
+    /// Path along planned steps, with the first steps searched again
+    /// among the ants of this turn. None if longer than the cutoff.
+    fn refine(
+        world: &dyn WorldStep,
+        planned: &[Position],
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> Option<SearchResult> {
+        let from = &planned[0];
+        let waypoint =
+            std::cmp::min(REFINED_STEPS, planned.len() - 1);
+        let refined = RepeatedAStar {}
+            .search(
+                world,
+                &[from.clone()].iter().cloned().collect(),
+                planned[waypoint].clone(),
+                1,
+                2 * waypoint,
+                deadline,
+            )
+            .into_iter()
+            .next()?;
+
+        let result = planned[waypoint + 1..]
+            .iter()
+            .fold(refined, |r, s| r.add_step(s.clone()));
+        if result.order_length() <= cutoff_len {
+            Some(result)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for HierarchicalSearch {
+    fn default() -> HierarchicalSearch {
+        HierarchicalSearch::new(10)
+    }
+}
+
+impl Search for HierarchicalSearch {
+    fn search(
+        &self,
+        world: &dyn WorldStep,
+        from: &HashSet<Position>,
+        to: Position,
+        max_result_len: usize,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> Vec<SearchResult> {
+        if !self.has_graph(world.size()) {
+            self.update_graph(world, deadline);
+        }
+        let graph = self.graph.read().expect("cluster graph lock");
+        let graph = graph.as_ref().expect("an updated graph");
+        let tree = graph.goal_tree(&to, cutoff_len);
+
+        search_each_origin(
+            world,
+            from,
+            &to,
+            max_result_len,
+            cutoff_len,
+            deadline,
+            |f, _, max_cost| {
+                graph.path_from(&tree, f, max_cost).and_then(
+                    |planned| {
+                        Self::refine(
+                            world, &planned, max_cost, deadline,
+                        )
+                    },
+                )
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::search::manhattan_filter::manhattan;
+    use crate::utilities::*;
+    use crate::world_step::AvoidWaterFilter;
+    use std::time::Duration;
+
+    const MAP: &str = "----------%---------
+                       ----%%%---%---%%%---
+                       ----%-----%-----%---
+                       ----%----------%----
+                       %%%%%%%%--%%%%%%----
+                       ----------%---------
+                       --%%%%----%--%%%%%--
+                       -----%--------%-----
+                       -----%----%---%-----
+                       ----------%---------";
+
+    #[test]
+    fn entrances_on_open_borders() {
+        let world = &AvoidWaterFilter::new_from_line_map(
+            "---%--
+             ---%--
+             %%----
+             ------",
+        );
+        let graph = ClusterGraph::new(world, 3);
+
+        // Clusters are 3 by 3, the last ones smaller. Between the two
+        // upper clusters only the lowest row is open.
+        assert!(!graph.entrances[&(0, 0)].contains(&pos(0, 2)));
+        assert!(graph.entrances[&(0, 0)].contains(&pos(2, 2)));
+        assert!(graph.entrances[&(0, 1)].contains(&pos(2, 3)));
+        // Open borders get an entrance in the middle.
+        assert!(graph.edges[&pos(2, 4)]
+            .iter()
+            .any(|e| e.to == pos(3, 4)));
+    }
+
+    #[test]
+    fn paths_avoid_water() {
+        let world = &AvoidWaterFilter::new_from_line_map(MAP);
+        let graph = ClusterGraph::new(world, 5);
+
+        let path =
+            graph.path(&pos(0, 0), &pos(9, 19), 40).expect("a path");
+
+        assert_eq!(path.first(), Some(&pos(0, 0)));
+        assert_eq!(path.last(), Some(&pos(9, 19)));
+        for step in path.windows(2) {
+            assert_eq!(
+                manhattan(&step[0], &step[1], world.size()),
+                1
+            );
+            assert!(world.tile(&step[1]) != Tile::Water);
+        }
+    }
+
+    #[test]
+    fn close_to_shortest_paths() {
+        let world = &AvoidWaterFilter::new_from_line_map(MAP);
+        let search = HierarchicalSearch::new(5);
+        let froms = positions_of('-', MAP);
+        let to = pos(7, 12);
+
+        let field = DistanceField::flood(
+            world,
+            &set![to.clone()],
+            60,
+            &Deadline::never(),
+        );
+        let results = search.search(
+            world,
+            &froms,
+            to,
+            1000,
+            60,
+            &Deadline::never(),
+        );
+
+        assert_eq!(results.len(), froms.len() - 1);
+        for result in results {
+            let shortest = field
+                .distance(&result.first_step())
+                .expect("reachable");
+            assert!(result.order_length() >= shortest);
+            assert!(
+                result.order_length() <= shortest + shortest / 2 + 4
+            );
+        }
+    }
+
+    #[test]
+    fn keep_graph_until_updated_with_new_water() {
+        let search = HierarchicalSearch::new(5);
+        let find = |map| {
+            let world = &AvoidWaterFilter::new_from_line_map(map);
+            search.search(
+                world,
+                &set![pos(0, 0)],
+                pos(0, 14),
+                1,
+                20,
+                &Deadline::never(),
+            )[0]
+            .order_length()
+        };
+        let update = |map| {
+            search.update_graph(
+                &AvoidWaterFilter::new_from_line_map(map),
+                &Deadline::never(),
+            )
+        };
+        let walled = "------------%-----------------";
+
+        // Water beyond the refined steps is only known by the graph.
+        assert_eq!(find("------------------------------"), 14);
+        assert_eq!(find(walled), 14);
+        update(walled);
+        assert_eq!(find(walled), 16);
+    }
+
+    type Summary = (
+        Vec<(Cluster, Vec<Position>)>,
+        Vec<(Position, Position, usize)>,
+    );
+
+    /// Entrances by cluster and edge costs by ends, comparable
+    /// between graphs.
+    fn summary(graph: &ClusterGraph) -> Summary {
+        let mut entrances: Vec<(Cluster, Vec<Position>)> = graph
+            .entrances
+            .iter()
+            .map(|(c, e)| {
+                let mut e = e.clone();
+                e.sort();
+                (*c, e)
+            })
+            .filter(|(_, e)| !e.is_empty())
+            .collect();
+        entrances.sort();
+        let mut edges: Vec<(Position, Position, usize)> = graph
+            .edges
+            .iter()
+            .flat_map(|(from, edges)| {
+                edges.iter().map(move |e| {
+                    (from.clone(), e.to.clone(), e.cost())
+                })
+            })
+            .collect();
+        edges.sort();
+        (entrances, edges)
+    }
+
+    #[test]
+    fn same_graph_when_adding_water_as_when_built_with_it() {
+        let before = &AvoidWaterFilter::new_from_line_map(
+            "--------------------
+             ----%---------------
+             --------------------
+             --------------------
+             --------------------
+             --------------------
+             --------------------
+             --------------------
+             --------------------
+             --------------------",
+        );
+        let after = &AvoidWaterFilter::new_from_line_map(MAP);
+        let mut graph = ClusterGraph::new(before, 5);
+
+        graph.add_water(after, &Deadline::never());
+
+        assert_eq!(
+            summary(&graph),
+            summary(&ClusterGraph::new(after, 5))
+        );
+    }
+
+    #[test]
+    fn add_no_water_after_deadline() {
+        let world = &AvoidWaterFilter::new_from_line_map(MAP);
+        let mut graph = ClusterGraph::without_water(world.size(), 5);
+
+        graph.add_water(
+            world,
+            &Deadline::after(Duration::from_millis(0)),
+        );
+
+        assert!(graph.has_new_water(world));
+        graph.add_water(world, &Deadline::never());
+        assert!(!graph.has_new_water(world));
+    }
+}
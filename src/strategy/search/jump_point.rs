@@ -0,0 +1,355 @@
+use super::manhattan_filter::manhattan;
+use crate::strategy::search::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+
+/// Jump point search, an A-star search on the 4-connected grid that
+/// only puts jump points in the queue. Straight runs without choices
+/// are skipped in one go, which saves most of the queue work on open
+/// maps.
+///
+/// Horizontal runs stop where a vertical move opens up that was not
+/// possible from the previous step, and vertical runs stop where any
+/// horizontal run finds a jump point. Runs wrap around the world
+/// borders like `manhattan` does, and never go beyond the cutoff.
+pub struct JumpPointSearch {}
+
+/// Number of expanded jump points between checks of the deadline.
+const DEADLINE_CHECK_INTERVAL: usize = 64;
+
+const DIRECTIONS: [Direction; 4] = [North, East, South, West];
+
+/// Index in DIRECTIONS of the direction used to reach a jump point,
+/// used since Direction is not ordered.
+type DirIndex = usize;
+
+/// Index of the start, reached by no direction.
+const START: DirIndex = 4;
+
+fn is_vertical(dir: DirIndex) -> bool {
+    matches!(DIRECTIONS[dir], North | South)
+}
+
+/// The two directions perpendicular to given one.
+fn sideways(dir: DirIndex) -> [DirIndex; 2] {
+    [(dir + 1) % 4, (dir + 3) % 4]
+}
+
+fn step(pos: &Position, dir: DirIndex, size: &Position) -> Position {
+    pos.order(DIRECTIONS[dir]).target_pos(size)
+}
+
+fn can_move(
+    world: &dyn WorldStep,
+    pos: &Position,
+    dir: DirIndex,
+) -> bool {
+    world.available_directions(pos).contains(&DIRECTIONS[dir])
+}
+
+/// Run from 'pos', reached after 'g' steps, in given direction until
+/// a jump point, giving the jump point and its distance. None if
+/// there is no jump point within the cutoff, counting the steps so
+/// far plus the Manhattan distance left to 'to'. The Manhattan
+/// distance may decrease along a run, but this sum never does, so
+/// the run can stop once it is past the cutoff.
+fn jump(
+    world: &dyn WorldStep,
+    pos: &Position,
+    dir: DirIndex,
+    to: &Position,
+    g: usize,
+    cutoff_len: usize,
+) -> Option<(Position, usize)> {
+    let size = world.size();
+    let mut current = pos.clone();
+    let mut distance = 0;
+
+    while can_move(world, &current, dir) {
+        let previous = current;
+        current = step(&previous, dir, size);
+        distance += 1;
+
+        if g + distance + manhattan(&current, to, size) as usize
+            > cutoff_len
+        {
+            return None;
+        }
+        if current == *to {
+            return Some((current, distance));
+        }
+
+        let forced = sideways(dir).iter().any(|&side| {
+            can_move(world, &current, side)
+                && !can_move(world, &previous, side)
+        });
+        if forced {
+            return Some((current, distance));
+        }
+
+        if is_vertical(dir) {
+            let turns = sideways(dir).iter().any(|&side| {
+                jump(
+                    world,
+                    &current,
+                    side,
+                    to,
+                    g + distance,
+                    cutoff_len,
+                )
+                .is_some()
+            });
+            if turns {
+                return Some((current, distance));
+            }
+        }
+    }
+    None
+}
+
+/// Directions to continue in from a jump point, reached by given
+/// direction. Going back is never shorter.
+fn successors(dir: DirIndex) -> Vec<DirIndex> {
+    if dir == START {
+        vec![0, 1, 2, 3]
+    } else {
+        let [left, right] = sideways(dir);
+        vec![dir, left, right]
+    }
+}
+
+/// Positions from 'from' to 'to' in a straight line.
+fn straight_steps(
+    from: &Position,
+    to: &Position,
+    dir: DirIndex,
+    size: &Position,
+) -> Vec<Position> {
+    let mut steps = vec![];
+    let mut current = from.clone();
+    while current != *to {
+        current = step(&current, dir, size);
+        steps.push(current.clone());
+    }
+    steps
+}
+
+impl JumpPointSearch {
+    /// Shortest path from 'from' to 'to' no longer than the cutoff.
+    fn single_search(
+        &self,
+        world: &dyn WorldStep,
+        from: &Position,
+        to: &Position,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> Option<SearchResult> {
+        type Node = (Position, DirIndex);
+
+        let size = world.size();
+        let mut queue = BinaryHeap::new();
+        let mut best: HashMap<Node, usize> = HashMap::new();
+        let mut parents: HashMap<Node, Node> = HashMap::new();
+
+        let start = (from.clone(), START);
+        best.insert(start.clone(), 0);
+        queue.push(Reverse((
+            manhattan(from, to, size) as usize,
+            0,
+            start,
+        )));
+
+        let mut iterations: usize = 0;
+
+        while let Some(Reverse((_, g, node))) = queue.pop() {
+            // Checking time is not free, so only check now and then.
+            iterations += 1;
+            if iterations % DEADLINE_CHECK_INTERVAL == 0
+                && deadline.is_passed()
+            {
+                return None;
+            }
+
+            if matches!(best.get(&node), Some(&b) if b < g) {
+                continue;
+            }
+            if node.0 == *to {
+                return Some(Self::path(&node, &parents, size));
+            }
+
+            for dir in successors(node.1) {
+                if let Some((next, distance)) =
+                    jump(world, &node.0, dir, to, g, cutoff_len)
+                {
+                    let next_g = g + distance;
+                    let f =
+                        next_g + manhattan(&next, to, size) as usize;
+                    let next_node = (next, dir);
+
+                    let is_better = match best.get(&next_node) {
+                        Some(&b) => next_g < b,
+                        None => true,
+                    };
+                    if f <= cutoff_len && is_better {
+                        best.insert(next_node.clone(), next_g);
+                        parents
+                            .insert(next_node.clone(), node.clone());
+                        queue.push(Reverse((f, next_g, next_node)));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Shortest path from 'from' to 'to', starting with the Manhattan
+    /// distance as cutoff and doubling it until a path is found or the
+    /// given cutoff is reached. Runs are only as long as the cutoff
+    /// allows, so on open maps the first tries are the cheap ones.
+    fn deepening_search(
+        &self,
+        world: &dyn WorldStep,
+        from: &Position,
+        to: &Position,
+        distance: usize,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> Option<SearchResult> {
+        let mut bound = std::cmp::min(distance, cutoff_len);
+        loop {
+            let found =
+                self.single_search(world, from, to, bound, deadline);
+            if found.is_some() || bound >= cutoff_len {
+                return found;
+            }
+            bound = std::cmp::min(cutoff_len, 2 * bound.max(1));
+        }
+    }
+
+    /// Full path to given jump point, filling in the straight runs
+    /// between jump points.
+    fn path(
+        last: &(Position, DirIndex),
+        parents: &HashMap<(Position, DirIndex), (Position, DirIndex)>,
+        size: &Position,
+    ) -> SearchResult {
+        let mut runs = vec![];
+        let mut node = last;
+        while let Some(parent) = parents.get(node) {
+            runs.push(straight_steps(
+                &parent.0, &node.0, node.1, size,
+            ));
+            node = parent;
+        }
+
+        runs.iter()
+            .rev()
+            .flatten()
+            .fold(SearchResult::start(node.0.clone()), |r, s| {
+                r.add_step(s.clone())
+            })
+    }
+}
+
+impl Search for JumpPointSearch {
+    fn search(
+        &self,
+        world: &dyn WorldStep,
+        from: &HashSet<Position>,
+        to: Position,
+        max_result_len: usize,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> Vec<SearchResult> {
+        search_each_origin(
+            world,
+            from,
+            &to,
+            max_result_len,
+            cutoff_len,
+            deadline,
+            |f, distance, max_cost| {
+                self.deepening_search(
+                    world, f, &to, distance, max_cost, deadline,
+                )
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world_step::AvoidWaterFilter;
+
+    fn path(steps: &[Position]) -> SearchResult {
+        steps[1..]
+            .iter()
+            .fold(SearchResult::start(steps[0].clone()), |r, s| {
+                r.add_step(s.clone())
+            })
+    }
+
+    #[test]
+    fn jump_to_corner_of_wall() {
+        let world = &AvoidWaterFilter::new_from_line_map(
+            "------
+             %%%---
+             ------",
+        );
+
+        assert_eq!(
+            jump(world, &pos(0, 0), 1, &pos(2, 0), 0, 10),
+            Some((pos(0, 3), 3))
+        );
+    }
+
+    #[test]
+    fn path_around_wall() {
+        let world = &AvoidWaterFilter::new_from_line_map(
+            "------%
+             %%%--%%
+             ------%
+             %%%%%%%",
+        );
+
+        let actual = JumpPointSearch {}.single_search(
+            world,
+            &pos(0, 0),
+            &pos(2, 0),
+            10,
+            &Deadline::never(),
+        );
+
+        assert_eq!(
+            actual.as_ref().map(|r| r.order_length()),
+            Some(8)
+        );
+        assert_eq!(
+            actual.map(|r| r.steps()[..4].to_vec()),
+            Some(vec![pos(0, 0), pos(0, 1), pos(0, 2), pos(0, 3)])
+        );
+    }
+
+    #[test]
+    fn wrap_around_world_border() {
+        let world = &AvoidWaterFilter::new_from_line_map(
+            "-%%%--
+             -%%%-%",
+        );
+
+        let actual = JumpPointSearch {}.single_search(
+            world,
+            &pos(1, 0),
+            &pos(0, 5),
+            10,
+            &Deadline::never(),
+        );
+
+        assert_eq!(
+            actual,
+            Some(path(&[pos(1, 0), pos(0, 0), pos(0, 5)]))
+        );
+    }
+}
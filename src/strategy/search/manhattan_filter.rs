@@ -24,6 +24,7 @@ impl<S:Search> Search for ManhattanFilter<S> {
         to: Position,
         max_result_len: usize,
         cutoff_len: usize,
+        deadline: &Deadline,
     ) -> Vec<SearchResult> {
         let limit = cutoff_len as u16;
         let size = world.size();
@@ -39,6 +40,7 @@ impl<S:Search> Search for ManhattanFilter<S> {
             to,
             max_result_len,
             cutoff_len,
+            deadline,
         )
     }
 }
@@ -63,6 +65,7 @@ mod tests {
             to: Position,
             max_result_len: usize,
             cutoff_len: usize,
+            _deadline: &Deadline,
         ) -> Vec<SearchResult> {
             assert_eq!(self.expected_to, to);
             assert_eq!(&self.expected_from, from);
@@ -97,7 +100,14 @@ mod tests {
                 expected_cutoff_len: 0,
             },
         }
-        .search(world, &original_from, to.clone(), 10, 0);
+        .search(
+            world,
+            &original_from,
+            to.clone(),
+            10,
+            0,
+            &Deadline::never(),
+        );
 
         ManhattanFilter {
             inner: MockedInner {
@@ -107,7 +117,14 @@ mod tests {
                 expected_cutoff_len: 1,
             },
         }
-        .search(world, &original_from, to.clone(), 10, 1);
+        .search(
+            world,
+            &original_from,
+            to.clone(),
+            10,
+            1,
+            &Deadline::never(),
+        );
 
         ManhattanFilter {
             inner: MockedInner {
@@ -117,7 +134,14 @@ mod tests {
                 expected_cutoff_len: 2,
             },
         }
-        .search(world, &original_from, to.clone(), 10, 2);
+        .search(
+            world,
+            &original_from,
+            to.clone(),
+            10,
+            2,
+            &Deadline::never(),
+        );
 
         ManhattanFilter {
             inner: MockedInner {
@@ -127,7 +151,14 @@ mod tests {
                 expected_cutoff_len: 3,
             },
         }
-        .search(world, &original_from, to.clone(), 13, 3);
+        .search(
+            world,
+            &original_from,
+            to.clone(),
+            13,
+            3,
+            &Deadline::never(),
+        );
 
         ManhattanFilter {
             inner: MockedInner {
@@ -137,6 +168,13 @@ mod tests {
                 expected_cutoff_len: 4,
             },
         }
-        .search(world, &original_from, to, 11, 4);
+        .search(
+            world,
+            &original_from,
+            to,
+            11,
+            4,
+            &Deadline::never(),
+        );
     }
 }
@@ -1,9 +1,17 @@
 pub mod bfs;
+pub mod bidirectional;
+pub mod distance_field;
+pub mod hierarchical;
+pub mod jump_point;
 pub mod manhattan_filter;
 pub mod repeated_a_star;
 
 use crate::strategy::*;
 pub use bfs::*;
+pub use bidirectional::*;
+pub use distance_field::*;
+pub use hierarchical::*;
+pub use jump_point::*;
 use manhattan_filter::*;
 pub use repeated_a_star::*;
 use std::collections::HashSet;
@@ -32,6 +40,10 @@ impl SearchResult {
             .expect("All search results has at least on step")
     }
 
+    pub fn steps(&self) -> &[Position] {
+        &self.steps
+    }
+
     pub fn order_length(&self) -> usize {
         self.steps.len() - 1
     }
@@ -75,6 +87,13 @@ impl SearchResult {
         result
     }
 
+    /// Remaining path after taking the first step.
+    pub fn without_first_step(&self) -> SearchResult {
+        SearchResult {
+            steps: self.steps[1..].to_vec(),
+        }
+    }
+
     pub fn reverse(&self) -> SearchResult {
         let mut result = SearchResult {
             steps: self.steps.clone(),
@@ -84,8 +103,9 @@ impl SearchResult {
     }
 }
 
-/// Search from multiple origins to a singel target.
-pub trait Search {
+/// Search from multiple origins to a singel target. Shareable
+/// between threads, e.g. by parallel multisearches.
+pub trait Search: Sync {
     /// Search nearest orders from 'from' to 'to'. The search can
     /// be limited/scoped by number of sought results and search
     /// length (effort) cutoff.
@@ -96,9 +116,90 @@ pub trait Search {
         to: Position,
         max_result_len: usize,
         cutoff_len: usize,
+        deadline: &Deadline,
     ) -> Vec<SearchResult>;
 }
 
+impl<S: Search + ?Sized> Search for &S {
+    fn search(
+        &self,
+        world: &dyn WorldStep,
+        from: &HashSet<Position>,
+        to: Position,
+        max_result_len: usize,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> Vec<SearchResult> {
+        (**self).search(
+            world,
+            from,
+            to,
+            max_result_len,
+            cutoff_len,
+            deadline,
+        )
+    }
+}
+
+/// Search from each origin separately with given single search,
+/// nearest origins by Manhattan distance first. The single search is
+/// given the origin, its Manhattan distance to 'to' and the cutoff,
+/// lowered to the longest result once enough results are found.
+/// Results are sorted shortest first.
+fn search_each_origin(
+    world: &dyn WorldStep,
+    from: &HashSet<Position>,
+    to: &Position,
+    max_result_len: usize,
+    cutoff_len: usize,
+    deadline: &Deadline,
+    mut single_search: impl FnMut(
+        &Position,
+        usize,
+        usize,
+    ) -> Option<SearchResult>,
+) -> Vec<SearchResult> {
+    let size = world.size();
+    let mut max_cost = cutoff_len;
+    let mut results: Vec<SearchResult> = vec![];
+
+    let mut sorted_froms: Vec<(usize, &Position)> = from
+        .iter()
+        .filter(|f| *f != to)
+        .map(|f| (manhattan(f, to, size) as usize, f))
+        .collect();
+    sorted_froms.sort();
+
+    for (distance, f) in sorted_froms {
+        if deadline.is_passed() {
+            break;
+        }
+        if max_cost < distance {
+            continue;
+        }
+
+        if let Some(result) = single_search(f, distance, max_cost) {
+            results.push(result);
+        }
+
+        if results.len() >= max_result_len {
+            // No need to search for results longer than the ones
+            // already found.
+            if let Some(longest) =
+                results.iter().map(|r| r.order_length()).max()
+            {
+                max_cost = std::cmp::min(max_cost, longest);
+            }
+        }
+    }
+
+    results.sort_by(|a, b| {
+        (a.order_length(), &a.steps).cmp(&(b.order_length(), &b.steps))
+    });
+    results.truncate(max_result_len);
+    results
+}
+
 /// Create default search algorithms
 pub fn create_search() -> Box<dyn Search> {
     Box::new(ManhattanFilter {
@@ -110,7 +211,24 @@ pub fn create_search() -> Box<dyn Search> {
 mod tests {
     use super::*;
     use crate::utilities::*;
-    use crate::world_step::{AvoidWaterFilter, BasicWorldStep};
+    use crate::world_step::{
+        AvoidWaterFilter, BasicWorldStep, CostLayer, Tile,
+    };
+
+    /// Search algorithms all passing the same tests.
+    fn searches() -> Vec<Box<dyn Search>> {
+        vec![
+            create_search(),
+            Box::new(JumpPointSearch {}),
+            Box::new(BidirectionalSearch {}),
+        ]
+    }
+
+    /// Search algorithms finding the cheapest paths by the step costs
+    /// of the world.
+    fn cost_aware_searches() -> Vec<Box<dyn Search>> {
+        vec![Box::new(RepeatedAStar {}), Box::new(BFS {})]
+    }
 
     fn assert_shortes_result_first(results: &[SearchResult]) {
         let mut min_len: usize = 0;
@@ -136,24 +254,27 @@ mod tests {
 
         let world = &AvoidWaterFilter::new_from_line_map(map);
 
-        let actual = create_search().search(
-            world,
-            froms,
-            to,
-            max_result_len,
-            cutoff_len,
-        );
+        for search in searches() {
+            let actual = search.search(
+                world,
+                froms,
+                to.clone(),
+                max_result_len,
+                cutoff_len,
+                &Deadline::never(),
+            );
 
-        assert_shortes_result_first(&actual);
+            assert_shortes_result_first(&actual);
 
-        let actual = actual
-            .iter()
-            .flat_map(|res| res.first_order(world.size()))
-            .collect::<HashSet<Order>>();
+            let actual = actual
+                .iter()
+                .flat_map(|res| res.first_order(world.size()))
+                .collect::<HashSet<Order>>();
 
-        let expected = orders(expected_first_orders);
+            let expected = orders(expected_first_orders);
 
-        assert_eq!(actual, expected);
+            assert_eq!(actual, expected);
+        }
     }
 
     #[test]
@@ -277,14 +398,17 @@ mod tests {
              %---a-",
         );
 
-        let actual = create_search().search(
-            world,
-            &set![pos(0, 4), pos(1, 5), pos(2, 4)],
-            pos(1, 1),
-            2,
-            20,
-        );
-        assert_eq!(actual.len(), 2);
+        for search in searches() {
+            let actual = search.search(
+                world,
+                &set![pos(0, 4), pos(1, 5), pos(2, 4)],
+                pos(1, 1),
+                2,
+                20,
+                &Deadline::never(),
+            );
+            assert_eq!(actual.len(), 2);
+        }
     }
 
     #[test]
@@ -293,6 +417,55 @@ mod tests {
         assert_first_order_from_a_to_b("b--a-%", "b--<-%", 1, 3);
     }
 
+    #[test]
+    fn passed_deadline_gives_no_results() {
+        let world = &AvoidWaterFilter::new_from_line_map("b-a--");
+
+        for search in
+            searches().into_iter().chain(cost_aware_searches())
+        {
+            let actual = search.search(
+                world,
+                &set![pos(0, 2)],
+                pos(0, 0),
+                10,
+                10,
+                &Deadline::after(std::time::Duration::from_millis(0)),
+            );
+            assert_eq!(actual, vec![]);
+        }
+    }
+
+    #[test]
+    fn around_costly_positions() {
+        // The enemy ant 'b' makes the straight way costly.
+        let costs = CostLayer::new_from_line_map(
+            "%%%%%%%
+             ------%
+             a-b---%
+             %%%%%%%",
+            0,
+            10,
+            0,
+        );
+        let world = &AvoidWaterFilter::new(costs);
+
+        for search in cost_aware_searches() {
+            let actual = search.search(
+                world,
+                &set![pos(2, 0)],
+                pos(2, 4),
+                1,
+                20,
+                &Deadline::never(),
+            );
+
+            assert_eq!(actual.len(), 1);
+            assert!(!actual[0].steps().contains(&pos(2, 2)));
+            assert_eq!(actual[0].order_length(), 6);
+        }
+    }
+
     /// Using a sizable world, in combination of searching for
     /// more results than there are solutions, might provoke
     /// unnecessary long computations.
@@ -304,11 +477,62 @@ mod tests {
         let world = world("b-a--");
         let size = pos(32_000, 32_000);
         let world_step = BasicWorldStep::new(world, size.clone());
-        let actual = create_search()
-            .search(&world_step, &set![pos(0, 2)], pos(0, 0), 2, 20)
-            .iter()
-            .flat_map(|result| result.first_order(&size))
-            .collect::<Vec<Order>>();
-        assert_eq![actual, vec![pos(0, 2).west()]];
+        for search in searches() {
+            let actual = search
+                .search(
+                    &world_step,
+                    &set![pos(0, 2)],
+                    pos(0, 0),
+                    2,
+                    20,
+                    &Deadline::never(),
+                )
+                .iter()
+                .flat_map(|result| result.first_order(&size))
+                .collect::<Vec<Order>>();
+            assert_eq![actual, vec![pos(0, 2).west()]];
+        }
+    }
+
+    #[test]
+    fn shortest_paths_like_distance_field() {
+        let map = "--%-------%---
+                   --%--%%%--%---
+                   -----%----%-%-
+                   %%%--%-----%--
+                   -----%%%%---%-
+                   --%------%----";
+        let world = &AvoidWaterFilter::new_from_line_map(map);
+        let to = pos(2, 7);
+        let froms: HashSet<Position> = (0..6)
+            .flat_map(|row| (0..14).map(move |col| pos(row, col)))
+            .filter(|p| world.tile(p) != Tile::Water && *p != to)
+            .collect();
+
+        let field = DistanceField::flood(
+            world,
+            &set![to.clone()],
+            30,
+            &Deadline::never(),
+        );
+
+        for search in searches() {
+            let results = search.search(
+                world,
+                &froms,
+                to.clone(),
+                100,
+                30,
+                &Deadline::never(),
+            );
+
+            assert_eq!(results.len(), froms.len());
+            for result in results {
+                assert_eq!(
+                    Some(result.order_length()),
+                    field.distance(&result.first_step())
+                );
+            }
+        }
     }
 }
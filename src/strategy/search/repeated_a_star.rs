@@ -10,9 +10,14 @@ use std::collections::HashSet;
 /// aiming for the same 'to' location.
 pub struct RepeatedAStar {}
 
+/// Number of search iterations between checks of the deadline.
+const DEADLINE_CHECK_INTERVAL: usize = 64;
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 struct Informed {
     sr: SearchResult,
+    /// Sum of the step costs of the search result.
+    cost: usize,
     forward_heuristic_cost: u16,
 }
 
@@ -37,6 +42,7 @@ impl PartialOrd for SearchResult {
 impl Informed {
     fn new(
         result: SearchResult,
+        cost: usize,
         target: &Position,
         world_size: &Position,
     ) -> Informed {
@@ -44,24 +50,30 @@ impl Informed {
             manhattan(&(result.last_step()), target, world_size);
         Informed {
             sr: result,
+            cost,
             forward_heuristic_cost: heuristic_cost,
         }
     }
-    fn total_cost(&self) -> u16 {
-        self.sr.order_length() as u16 + self.forward_heuristic_cost
+    fn total_cost(&self) -> usize {
+        self.cost + self.forward_heuristic_cost as usize
     }
+    /// Search result one step further. Searching backwards, the cost
+    /// is the cost of moving onto the current last step, from the
+    /// new one.
     fn go_forward(
         &self,
         dir: Direction,
         target: &Position,
-        world_size: &Position,
+        world: &dyn WorldStep,
     ) -> Informed {
-        let order = self.sr.last_step().order(dir);
-        let new_pos = order.target_pos(world_size);
+        let world_size = world.size();
+        let last_step = self.sr.last_step();
+        let new_pos = last_step.order(dir).target_pos(world_size);
         let fwd_cost = manhattan(&new_pos, target, world_size);
 
         Informed {
             sr: self.sr.add_step(new_pos),
+            cost: self.cost + world.step_cost(&last_step),
             forward_heuristic_cost: fwd_cost,
         }
     }
@@ -94,46 +106,69 @@ impl PartialOrd for Informed {
 }
 
 impl RepeatedAStar {
+    /// Cheapest path from 'from' to 'to', where 'visited' keeps the
+    /// cheapest paths from 'from' found so far, with their costs.
     fn single_search(
         &self,
         world: &dyn WorldStep,
         from: Position,
         to: Position,
         cutoff_len: usize,
-        visited: &mut HashMap<Position, SearchResult>,
-    ) -> Option<SearchResult> {
+        visited: &mut HashMap<Position, (usize, SearchResult)>,
+        deadline: &Deadline,
+    ) -> Option<(usize, SearchResult)> {
         let size = world.size();
         let mut queue: BinaryHeap<Informed> = BinaryHeap::new();
 
         queue.push(Informed::new(
             SearchResult::start(from),
+            0,
             &to,
             &size,
         ));
 
-        for (_pos, sr) in visited.iter() {
-            queue.push(Informed::new(sr.clone(), &to, &size));
+        for (_pos, (cost, sr)) in visited.iter() {
+            queue.push(Informed::new(sr.clone(), *cost, &to, &size));
         }
 
+        let mut iterations: usize = 0;
+
         while !queue.is_empty() {
+            // Checking time is not free, so only check now and then.
+            iterations += 1;
+            if iterations % DEADLINE_CHECK_INTERVAL == 0
+                && deadline.is_passed()
+            {
+                return None;
+            }
+
             let informed =
                 queue.pop().expect("queue should return something");
 
             let sr = informed.sr.clone();
             if sr.last_step() == to {
-                return Some(sr);
+                return Some((informed.cost, sr));
             }
 
             world
                 .available_directions(&sr.last_step())
                 .iter()
-                .map(|&dir| informed.go_forward(dir, &to, &size))
+                .map(|&dir| informed.go_forward(dir, &to, world))
                 .for_each(|targeted| {
                     let step = targeted.sr.last_step();
-                    if !visited.contains_key(&step)
-                        && targeted.total_cost() <= cutoff_len as u16
+                    // Revisit positions only if cheaper this way,
+                    // which can only happen with varying step costs.
+                    let is_cheaper = match visited.get(&step) {
+                        Some((cost, _)) => targeted.cost < *cost,
+                        None => true,
+                    };
+                    if is_cheaper
+                        && targeted.total_cost() <= cutoff_len
                     {
-                        visited.insert(step, targeted.sr.clone());
+                        visited.insert(
+                            step,
+                            (targeted.cost, targeted.sr.clone()),
+                        );
                         queue.push(targeted);
                     }
                 });
@@ -144,9 +179,9 @@ impl RepeatedAStar {
 }
 
 impl Search for RepeatedAStar {
-    /// Nearest orders to 'to' from 'from', is actually a reversed breadth first
-    /// search starting with 'to' and searching for the first matching positions
-    /// in 'from'.
+    /// Cheapest orders to 'to' from 'from', by the step costs of the
+    /// world. Searches backwards from 'to', reusing the paths found
+    /// for earlier origins.
     fn search(
         &self,
         world: &dyn WorldStep,
@@ -154,19 +189,21 @@ impl Search for RepeatedAStar {
         to: Position,
         max_result_len: usize,
         cutoff_len: usize,
+        deadline: &Deadline,
     ) -> Vec<SearchResult> {
-        let mut visited: HashMap<Position, SearchResult> =
+        let mut visited: HashMap<Position, (usize, SearchResult)> =
             HashMap::new();
 
-        let mut max_cost = cutoff_len as u16;
+        let mut max_cost = cutoff_len;
 
-        let mut results: BinaryHeap<SearchResult> = BinaryHeap::new();
+        let mut results: Vec<(usize, SearchResult)> = vec![];
 
         let mut sorted_froms: Vec<_> = from
             .iter()
             .map(|f| {
                 Informed::new(
                     SearchResult::start(f.clone()),
+                    0,
                     &to,
                     &world.size(),
                 )
@@ -180,14 +217,19 @@ impl Search for RepeatedAStar {
                 continue 'foo;
             }
 
-            if let Some(search_result) = self.single_search(
+            if deadline.is_passed() {
+                break 'foo;
+            }
+
+            if let Some((cost, search_result)) = self.single_search(
                 world,
                 to.clone(),
                 f.sr.last_step(),
-                max_cost as usize,
+                max_cost,
                 &mut visited,
+                deadline,
             ) {
-                results.push(search_result.reverse());
+                results.push((cost, search_result.reverse()));
             }
 
             if results.len() >= max_result_len {
@@ -197,10 +239,8 @@ impl Search for RepeatedAStar {
                 // already given results and we are only
                 // interested in even better results.
 
-                if let Some(max_result_cost) = results
-                    .iter()
-                    .map(|sr| sr.order_length() as u16)
-                    .max()
+                if let Some(max_result_cost) =
+                    results.iter().map(|(cost, _)| *cost).max()
                 {
                     max_cost =
                         std::cmp::min(max_cost, max_result_cost);
@@ -208,18 +248,19 @@ impl Search for RepeatedAStar {
             }
         }
 
-        // We want the shortest results first
-        let mut x = results.into_sorted_vec();
-        x.reverse();
-        x.truncate(max_result_len);
-        x
+        // We want the cheapest results first
+        results.sort_by(|(a_cost, a), (b_cost, b)| {
+            (a_cost, a.steps()).cmp(&(b_cost, b.steps()))
+        });
+        results.truncate(max_result_len);
+        results.into_iter().map(|(_, sr)| sr).collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::world_step::AvoidWaterFilter;
+    use crate::world_step::{AvoidWaterFilter, CostLayer};
 
     #[test]
     fn single_from_search_default() {
@@ -236,14 +277,43 @@ mod tests {
             pos(1, 1),
             100,
             &mut HashMap::new(),
+            &Deadline::never(),
         );
 
-        let expected = Some(
+        let expected = Some((
+            2,
             SearchResult::start(pos(1, 3))
                 .add_step(pos(1, 2))
                 .add_step(pos(1, 1)),
-        );
+        ));
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn cutoff_limits_cost() {
+        let costs = CostLayer::new_from_line_map(
+            "%%%%%%%
+             %%%%%%%
+             a-b---%
+             %%%%%%%",
+            0,
+            10,
+            0,
+        );
+        let world = &AvoidWaterFilter::new(costs);
+        let search = |cutoff_len| {
+            RepeatedAStar {}.search(
+                world,
+                &set![pos(2, 0)],
+                pos(2, 4),
+                1,
+                cutoff_len,
+                &Deadline::never(),
+            )
+        };
+
+        assert_eq!(search(13).len(), 0);
+        assert_eq!(search(14).len(), 1);
+    }
 }
@@ -1,8 +1,88 @@
 use crate::strategy::*;
 use ants_ai_challenge_api::Orders;
 use ants_ai_challenge_api::Position;
+use std::collections::HashMap;
 
-pub struct SpreadOutScout {}
+/// Name of the missions given by SpreadOutScout.
+const MISSION: &str = "spread_out_scout";
+
+/// Spreads out ants to the points of a grid. The grid point each ant
+/// is heading to is kept as the mission of the tracked ant, letting
+/// it continue there on later turns instead of being reassigned each
+/// turn. Free ants near never seen positions explore them first, if
+/// the unexplored field of the turn is flooded.
+pub struct SpreadOutScout {
+    /// Distance between the grid points the ants spread out to.
+    grid_step: u16,
+    max_result_len: usize,
+    cutoff_len: usize,
+}
+
+impl SpreadOutScout {
+    pub fn new(
+        grid_step: u16,
+        max_result_len: usize,
+        cutoff_len: usize,
+    ) -> SpreadOutScout {
+        SpreadOutScout {
+            grid_step,
+            max_result_len,
+            cutoff_len,
+        }
+    }
+
+    pub fn grid_step(&self) -> u16 {
+        self.grid_step
+    }
+
+    pub fn max_result_len(&self) -> usize {
+        self.max_result_len
+    }
+
+    pub fn cutoff_len(&self) -> usize {
+        self.cutoff_len
+    }
+
+    /// Paths of available ants to given targets, also giving the
+    /// ants not yet at their target the mission to get there.
+    fn paths_to_targets(
+        &self,
+        targets: &HashMap<Position, usize>,
+        world_step: &dyn WorldStep,
+        ants: &mut HashSet<Position>,
+        tracker: &mut AntTracker,
+        deadline: &Deadline,
+    ) -> Orders {
+        let size = world_step.size();
+        let paths = best_paths_to_target_with_capacity(
+            targets,
+            world_step,
+            ants,
+            self.max_result_len,
+            self.cutoff_len,
+            deadline,
+        );
+
+        let mut orders = vec![];
+        for path in paths {
+            if let Some(order) = path.first_order(size) {
+                if order.target_pos(size) == path.last_step() {
+                    tracker.clear_mission(&order.pos);
+                } else {
+                    tracker.set_mission(Mission::new(MISSION, path));
+                }
+                orders.push(order);
+            }
+        }
+        orders
+    }
+}
+
+impl Default for SpreadOutScout {
+    fn default() -> SpreadOutScout {
+        SpreadOutScout::new(7, 2, 30)
+    }
+}
 
 fn grid_points(size: &Position, step: &Position) -> Vec<Position> {
     let mut result: Vec<Position> = vec![];
@@ -18,16 +98,174 @@ fn grid_points(size: &Position, step: &Position) -> Vec<Position> {
 
 impl Strategy for SpreadOutScout {
     fn apply(
-        &self,
+        &mut self,
         world_step: &dyn WorldStep,
         ants_available: &mut HashSet<Position>,
+        deadline: &Deadline,
     ) -> Orders {
-        best_orders_to_target(
-            &grid_points(world_step.size(), &pos(7, 7)),
+        self.apply_tracked(
             world_step,
             ants_available,
-            2,
-            30,
+            &mut AntTracker::default(),
+            deadline,
         )
     }
+
+    /// Ants on a mission to a grid point continue there first, the
+    /// remaining ants spread out to the other grid points.
+    fn apply_tracked(
+        &mut self,
+        world_step: &dyn WorldStep,
+        ants_available: &mut HashSet<Position>,
+        tracker: &mut AntTracker,
+        deadline: &Deadline,
+    ) -> Orders {
+        let mut assigned_ants = HashSet::new();
+        let mut kept = HashMap::<Position, usize>::new();
+        for ant in ants_available.iter() {
+            match tracker.mission(ant) {
+                Some(m) if m.strategy == MISSION => {
+                    assigned_ants.insert(ant.clone());
+                    *kept.entry(m.target.clone()).or_insert(0) += 1;
+                }
+                _ => {}
+            }
+        }
+
+        let mut orders = self.paths_to_targets(
+            &kept,
+            world_step,
+            &mut assigned_ants,
+            tracker,
+            deadline,
+        );
+        for order in orders.iter() {
+            ants_available.remove(&order.pos);
+        }
+        for ant in assigned_ants.iter() {
+            tracker.clear_mission(ant);
+        }
+
+        if let Some(fields) = world_step.distance_fields() {
+            let unexplored: HashMap<Position, usize> = fields
+                .unexplored
+                .sources()
+                .iter()
+                .map(|p| (p.clone(), 1))
+                .collect();
+
+            orders.extend(
+                best_paths_along_field(
+                    &fields.unexplored,
+                    &unexplored,
+                    world_step,
+                    ants_available,
+                    self.cutoff_len,
+                )
+                .iter()
+                .flat_map(|r| r.first_order(world_step.size())),
+            );
+        }
+
+        let step = pos(self.grid_step, self.grid_step);
+        let free: HashMap<Position, usize> =
+            grid_points(world_step.size(), &step)
+                .into_iter()
+                .filter(|p| !kept.contains_key(p))
+                .map(|p| (p, 1))
+                .collect();
+
+        orders.extend(self.paths_to_targets(
+            &free,
+            world_step,
+            ants_available,
+            tracker,
+            deadline,
+        ));
+        orders
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world_step::*;
+
+    #[test]
+    fn ant_continues_to_remembered_grid_point() {
+        let mut strategy = SpreadOutScout::new(5, 2, 30);
+        let first_turn = &BasicWorldStep::new_from_line_map(
+            "--------------
+             --------------
+             --------------
+             --------------
+             --------------
+             -a------------",
+        );
+        let mut tracker = AntTracker::default();
+        tracker.update(&first_turn.all_my_ants());
+        let mut ants = set![pos(5, 1)];
+
+        let orders = strategy.apply_tracked(
+            first_turn,
+            &mut ants,
+            &mut tracker,
+            &Deadline::never(),
+        );
+
+        assert_eq![orders, vec![pos(5, 1).east()]];
+        tracker.record_orders(&orders, first_turn.size());
+
+        // Without remembering, the new ant nearer to (5, 5) would be
+        // sent there instead.
+        let second_turn = &BasicWorldStep::new_from_line_map(
+            "--------------
+             --------------
+             --------------
+             --------------
+             --------------
+             --a---a-------",
+        );
+        tracker.update(&second_turn.all_my_ants());
+        let mut ants = set![pos(5, 2), pos(5, 6)];
+
+        let mut orders = strategy.apply_tracked(
+            second_turn,
+            &mut ants,
+            &mut tracker,
+            &Deadline::never(),
+        );
+        orders.sort_by_key(|o| o.pos.clone());
+
+        assert_eq![orders, vec![pos(5, 2).east(), pos(5, 6).east()]];
+        assert_eq![ants, set![]];
+        let mission = tracker.mission(&pos(5, 2)).expect("mission");
+        assert_eq![mission.target, pos(5, 5)];
+    }
+
+    #[test]
+    fn explore_unseen_positions_before_grid_points() {
+        let mut strategy = SpreadOutScout::new(5, 2, 30);
+        let world_step = &FieldLayer::new(
+            VisibilityLayer::new_from_line_map(
+                "??????????
+                 ----------
+                 ----------
+                 -----a----
+                 ----------
+                 ----------",
+                2,
+            ),
+            30,
+            &Deadline::never(),
+        );
+        let mut ants = set![pos(3, 5)];
+
+        let orders =
+            strategy.apply(world_step, &mut ants, &Deadline::never());
+
+        // Without the field, the ant would head south to (5, 5).
+        assert_eq![orders, vec![pos(3, 5).north()]];
+        assert_eq![ants, set![]];
+    }
 }
@@ -67,6 +67,16 @@ pub fn positions_of(
         .collect()
 }
 
+/// Retrieves all positions in a 2D coordinate system, that are not
+/// marked as unseen territory with character '?'.
+pub fn seen_positions(multi_lines: &str) -> HashSet<Position> {
+    chars_with_pos(multi_lines)
+        .into_iter()
+        .filter(|(ch, _)| *ch != '?')
+        .map(|(_, pos)| pos)
+        .collect()
+}
+
 /// Retrieves all orders from character 2D coordinate system.
 /// - 'W', 'w' and '<' indicates an order directed to the west.
 /// - 'E', 'e' and '>' indicates an order directed to the east.
@@ -347,6 +357,17 @@ mod tests {
         )
     }
 
+    #[test]
+    fn seen_positions_success() {
+        assert_eq!(
+            set!(pos(0, 0), pos(0, 2), pos(1, 1)),
+            seen_positions(
+                "a?*
+                 ?.?"
+            )
+        )
+    }
+
     #[test]
     fn positions_of_success() {
         assert_eq!(
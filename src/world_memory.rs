@@ -0,0 +1,305 @@
+use ants_ai_challenge_api::*;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+/// Number of turns an enemy ant out of sight is remembered.
+const ENEMY_ANT_MEMORY_TURNS: u32 = 10;
+
+/// A remembered world feature, with owning player and the turn it
+/// was last seen.
+#[derive(Debug, Clone, PartialEq)]
+struct Remembered {
+    owner: u8,
+    last_seen: u32,
+}
+
+/// Memory of world features seen in earlier turns, that might be
+/// hidden in the fog of war in the current turn. Remembered food,
+/// hills and enemy ants are forgotten when their position is visible
+/// and they are no longer there.
+#[derive(Default)]
+pub struct WorldMemory {
+    waters: HashSet<Position>,
+    seen: HashSet<Position>,
+    foods: HashMap<Position, Remembered>,
+    hills: HashMap<Position, Remembered>,
+    enemy_ants: HashMap<Position, Remembered>,
+}
+
+fn forget_visible(
+    memory: &mut HashMap<Position, Remembered>,
+    visible: &HashSet<Position>,
+) {
+    memory.retain(|p, _| !visible.contains(p));
+}
+
+fn sorted_keys(
+    memory: &HashMap<Position, Remembered>,
+) -> Vec<(&Position, &Remembered)> {
+    let mut result: Vec<_> = memory.iter().collect();
+    result.sort_by_key(|(p, _)| (*p).clone());
+    result
+}
+
+impl WorldMemory {
+    /// Update memory with the world state of the given turn, where
+    /// 'visible' are all positions currently seen by my ants.
+    pub fn update(
+        &mut self,
+        world: &WorldState,
+        turn: u32,
+        visible: &HashSet<Position>,
+    ) {
+        self.waters.extend(world.waters.iter().cloned());
+        self.seen.extend(visible.iter().cloned());
+
+        forget_visible(&mut self.foods, visible);
+        forget_visible(&mut self.hills, visible);
+        forget_visible(&mut self.enemy_ants, visible);
+
+        self.enemy_ants.retain(|_, r| {
+            turn < r.last_seen + ENEMY_ANT_MEMORY_TURNS
+        });
+
+        for food in &world.foods {
+            self.foods.insert(
+                food.clone(),
+                Remembered {
+                    owner: 0,
+                    last_seen: turn,
+                },
+            );
+        }
+
+        for (owner, hills) in world.hills.iter().enumerate() {
+            for hill in hills {
+                self.hills.insert(
+                    hill.clone(),
+                    Remembered {
+                        owner: owner as u8,
+                        last_seen: turn,
+                    },
+                );
+            }
+        }
+
+        for (owner, ants) in
+            world.live_ants.iter().enumerate().skip(1)
+        {
+            for ant in ants {
+                self.enemy_ants.insert(
+                    ant.clone(),
+                    Remembered {
+                        owner: owner as u8,
+                        last_seen: turn,
+                    },
+                );
+            }
+        }
+    }
+
+    /// All positions seen so far.
+    pub fn seen(&self) -> &HashSet<Position> {
+        &self.seen
+    }
+
+    /// Turn when food, hill or enemy ant at position was last seen.
+    pub fn last_seen(&self, p: &Position) -> Option<u32> {
+        [&self.foods, &self.hills, &self.enemy_ants]
+            .iter()
+            .flat_map(|memory| memory.get(p))
+            .map(|r| r.last_seen)
+            .max()
+    }
+
+    /// Turn each remembered food, hill and enemy ant was last seen,
+    /// by position.
+    pub fn last_seen_positions(&self) -> HashMap<Position, u32> {
+        let mut result = HashMap::new();
+        for memory in &[&self.foods, &self.hills, &self.enemy_ants] {
+            for (p, r) in memory.iter() {
+                let turn = result.entry(p.clone()).or_insert(0);
+                *turn = std::cmp::max(*turn, r.last_seen);
+            }
+        }
+        result
+    }
+
+    /// Enemy ants seen in the last turns, with owner and the turn
+    /// they were last seen, sorted by position. They are not
+    /// part of the remembered world, since they have probably moved.
+    pub fn enemy_ants(&self) -> Vec<(Position, u8, u32)> {
+        sorted_keys(&self.enemy_ants)
+            .into_iter()
+            .map(|(p, r)| (p.clone(), r.owner, r.last_seen))
+            .collect()
+    }
+
+    /// The current world state, complemented with the food, hills and
+    /// water remembered from earlier turns. Only the ants seen in the
+    /// current turn are included.
+    pub fn remembered_world(&self, world: &WorldState) -> WorldState {
+        let mut waters: Vec<Position> =
+            self.waters.iter().cloned().collect();
+        waters.sort();
+
+        let mut result = WorldState {
+            dead_ants: world.dead_ants.clone(),
+            foods: sorted_keys(&self.foods)
+                .into_iter()
+                .map(|(p, _)| p.clone())
+                .collect(),
+            hills: vec![],
+            live_ants: world.live_ants.clone(),
+            waters,
+        };
+
+        for (p, r) in sorted_keys(&self.hills) {
+            result = result.hill(p.clone(), r.owner);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::*;
+    use crate::world_step::positions_within;
+
+    fn update(
+        memory: &mut WorldMemory,
+        map: &'static str,
+        turn: u32,
+    ) -> WorldState {
+        let world = world(map);
+        let visible = positions_within(
+            &world.live_ants_for_player(0),
+            2,
+            &size_of_world(map),
+        );
+        memory.update(&world, turn, &visible);
+        memory.remembered_world(&world)
+    }
+
+    #[test]
+    fn remember_food_and_hills_out_of_sight() {
+        let mut memory = WorldMemory::default();
+
+        update(
+            &mut memory,
+            "-*1------
+             -a-------
+             ---------",
+            1,
+        );
+
+        let remembered = update(
+            &mut memory,
+            "---------
+             ---------
+             --------a",
+            21,
+        );
+
+        assert_eq![remembered.foods, vec![pos(0, 1)]];
+        assert_eq![remembered.hills.get(1), Some(&vec![pos(0, 2)])];
+        assert_eq![memory.last_seen(&pos(0, 2)), Some(1)];
+    }
+
+    #[test]
+    fn forget_visible_food_and_hills_no_longer_there() {
+        let mut memory = WorldMemory::default();
+
+        update(
+            &mut memory,
+            "-*1------
+             -a-------
+             ---------",
+            1,
+        );
+
+        let remembered = update(
+            &mut memory,
+            "---------
+             -a-------
+             ---------",
+            2,
+        );
+
+        assert_eq![remembered.foods, vec![]];
+        assert_eq![remembered.hills.get(1), None];
+        assert_eq![memory.last_seen(&pos(0, 1)), None];
+    }
+
+    #[test]
+    fn forget_enemy_ants_after_a_while() {
+        let mut memory = WorldMemory::default();
+
+        update(
+            &mut memory,
+            "-b-------
+             -a-------
+             ---------",
+            1,
+        );
+
+        update(
+            &mut memory,
+            "---------
+             ---------
+             -------a-",
+            1 + ENEMY_ANT_MEMORY_TURNS - 1,
+        );
+        assert_eq![memory.enemy_ants(), vec![(pos(0, 1), 1, 1)]];
+
+        update(
+            &mut memory,
+            "---------
+             ---------
+             -------a-",
+            1 + ENEMY_ANT_MEMORY_TURNS,
+        );
+        assert_eq![memory.enemy_ants(), vec![]];
+    }
+
+    #[test]
+    fn only_ants_seen_this_turn_are_live() {
+        let mut memory = WorldMemory::default();
+
+        update(
+            &mut memory,
+            "-b-------
+             -a-----b-
+             ---------",
+            1,
+        );
+
+        let remembered = update(
+            &mut memory,
+            "---------
+             -------b-
+             -------a-",
+            2,
+        );
+        assert_eq![
+            remembered.live_ants,
+            vec![vec![pos(2, 7)], vec![pos(1, 7)]]
+        ];
+        assert_eq![
+            memory.enemy_ants(),
+            vec![(pos(0, 1), 1, 1), (pos(1, 7), 1, 2)]
+        ];
+    }
+
+    #[test]
+    fn remember_water() {
+        let mut memory = WorldMemory::default();
+
+        update(&mut memory, "a%-", 1);
+        let remembered = update(&mut memory, "a--", 2);
+
+        assert_eq![remembered.waters, vec![pos(0, 1)]];
+    }
+}
@@ -6,78 +6,93 @@ use std::iter::FromIterator;
 
 pub struct AntCrashFilter<S: WorldStep> {
     delegate: S,
+    /// Target positions of the orders given so far.
+    targets: HashSet<Position>,
 }
 
 impl AntCrashFilter<BasicWorldStep> {
     #[cfg(test)]
     pub fn new_from_line_map(map: &'static str) -> AntCrashFilter<BasicWorldStep> {
         let inner = BasicWorldStep::new_from_line_map(map);
-        AntCrashFilter {
-            delegate: inner,
+        AntCrashFilter::new(inner)
+    }
+
+}
+
+/// Given orders of my ants that can be executed without ants
+/// crashing into each other, in an order they can be executed in.
+pub fn executable_orders(
+    given_orders: Orders,
+    my_ants: &[Position],
+    size: &Position,
+) -> Orders {
+    let mut unmoved_ants: HashSet<Position> =
+        HashSet::from_iter(my_ants.iter().cloned());
+
+    let mut moved_ants: HashSet<Position> = HashSet::new();
+
+    let mut awaiting_orders: HashMap<Position, Order> =
+        HashMap::new();
+
+    let mut executed_orders: Vec<Order> = vec![];
+
+    for order in given_orders {
+        if unmoved_ants.contains(&order.pos) {
+            let target = order.target_pos(size);
+            if moved_ants.contains(&target) {
+                // No chance for this order  to be executed - dropped
+            } else if unmoved_ants.contains(&target) {
+                awaiting_orders
+                    .entry(target.clone())
+                    .or_insert(order);
+            } else {
+                executed_orders.push(order.clone());
+                moved_ants.insert(target);
+                unmoved_ants.remove(&order.pos);
+
+                // Keep resolving old awaiting orders, who's target
+                // is the same as this (just executed) order's source.
+                let mut source = order.clone().pos;
+                while awaiting_orders.contains_key(&source) {
+                    let awaiting_order =
+                        awaiting_orders[&source].clone();
+
+                    executed_orders.push(awaiting_order.clone());
+                    moved_ants.insert(source);
+
+                    source = awaiting_order.pos.clone();
+                }
+            }
+        } else {
+            // Error handling please!
         }
     }
 
+    executed_orders
 }
 
 impl<S:WorldStep> AntCrashFilter<S> {
     pub fn new(delegate: S) -> AntCrashFilter<S> {
-        AntCrashFilter { delegate }
+        AntCrashFilter {
+            delegate,
+            targets: HashSet::new(),
+        }
     }
 }
 
 impl<S:WorldStep> WorldStep for AntCrashFilter<S> {
     fn add_order(&mut self, order: Order) -> &mut dyn WorldStep {
+        self.targets.insert(order.target_pos(self.size()));
         self.delegate.add_order(order);
         self
     }
 
     fn get_orders(&self) -> Orders {
-        let given_orders = self.delegate.get_orders();
-
-        let mut unmoved_ants: HashSet<Position> = HashSet::from_iter(
-            self.delegate.all_my_ants().iter().cloned(),
-        );
-
-        let mut moved_ants: HashSet<Position> = HashSet::new();
-
-        let mut awaiting_orders: HashMap<Position, Order> =
-            HashMap::new();
-
-        let mut executed_orders: Vec<Order> = vec![];
-
-        for order in given_orders {
-            if unmoved_ants.contains(&order.pos) {
-                let target = order.target_pos(self.size());
-                if moved_ants.contains(&target) {
-                    // No chance for this order  to be executed - dropped
-                } else if unmoved_ants.contains(&target) {
-                    awaiting_orders
-                        .entry(target.clone())
-                        .or_insert(order);
-                } else {
-                    executed_orders.push(order.clone());
-                    moved_ants.insert(target);
-                    unmoved_ants.remove(&order.pos);
-
-                    // Keep resolving old awaiting orders, who's target
-                    // is the same as this (just executed) order's source.
-                    let mut source = order.clone().pos;
-                    while awaiting_orders.contains_key(&source) {
-                        let awaiting_order =
-                            awaiting_orders[&source].clone();
-
-                        executed_orders.push(awaiting_order.clone());
-                        moved_ants.insert(source);
-
-                        source = awaiting_order.pos.clone();
-                    }
-                }
-            } else {
-                // Error handling please!
-            }
-        }
-
-        executed_orders
+        executable_orders(
+            self.delegate.get_orders(),
+            &self.delegate.all_my_ants(),
+            self.size(),
+        )
     }
 
     fn size(&self) -> &Position {
@@ -89,13 +104,6 @@ impl<S:WorldStep> WorldStep for AntCrashFilter<S> {
     }
 
     fn available_directions(&self, p: &Position) -> Vec<Direction> {
-        let known_targets: Vec<_> = self
-            .delegate
-            .get_orders()
-            .iter()
-            .map(|order| order.target_pos(&self.size()))
-            .collect();
-
         self.delegate
             .available_directions(p)
             .iter()
@@ -103,7 +111,7 @@ impl<S:WorldStep> WorldStep for AntCrashFilter<S> {
             .filter(|dir| {
                 let dir_target =
                     p.order(*dir).target_pos(self.size());
-                !known_targets.contains(&dir_target)
+                !self.targets.contains(&dir_target)
             })
             .collect()
     }
@@ -115,6 +123,26 @@ impl<S:WorldStep> WorldStep for AntCrashFilter<S> {
     fn get_positions(&self, tile: Tile) -> Vec<Position> {
         self.delegate.get_positions(tile)
     }
+
+    fn is_visible(&self, pos: &Position) -> bool {
+        self.delegate.is_visible(pos)
+    }
+
+    fn step_cost(&self, pos: &Position) -> usize {
+        self.delegate.step_cost(pos)
+    }
+
+    fn distance_fields(&self) -> Option<&DistanceFields> {
+        self.delegate.distance_fields()
+    }
+
+    fn last_seen(&self, pos: &Position) -> Option<u32> {
+        self.delegate.last_seen(pos)
+    }
+
+    fn remembered_enemy_ants(&self) -> Vec<(Position, u8, u32)> {
+        self.delegate.remembered_enemy_ants()
+    }
 }
 
 #[cfg(test)]
@@ -70,9 +70,29 @@ where
         self.delegate.tile(pos)
     }
 
+    fn step_cost(&self, pos: &Position) -> usize {
+        self.delegate.step_cost(pos)
+    }
+
+    fn distance_fields(&self) -> Option<&DistanceFields> {
+        self.delegate.distance_fields()
+    }
+
+    fn last_seen(&self, pos: &Position) -> Option<u32> {
+        self.delegate.last_seen(pos)
+    }
+
+    fn remembered_enemy_ants(&self) -> Vec<(Position, u8, u32)> {
+        self.delegate.remembered_enemy_ants()
+    }
+
     fn get_positions(&self, tile: Tile) -> Vec<Position> {
         self.delegate.get_positions(tile)
     }
+
+    fn is_visible(&self, pos: &Position) -> bool {
+        self.delegate.is_visible(pos)
+    }
 }
 #[cfg(test)]
 mod tests {
@@ -99,9 +99,19 @@ impl WorldStep for BasicWorldStep {
                 Some(hills) => hills.clone(),
                 None => vec![],
             },
+            Tile::Ant(p) => {
+                match self.world.live_ants.get(p as usize) {
+                    Some(ants) => ants.clone(),
+                    None => vec![],
+                }
+            }
             _ => vec![],
         }
     }
+
+    fn is_visible(&self, _pos: &Position) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
@@ -133,6 +143,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn get_hills_and_ants_of_all_enemies() {
+        let step = BasicWorldStep::new_from_line_map(
+            "02-
+             -1c
+             2-a",
+        );
+
+        assert_eq!(
+            vec![pos(1, 1), pos(0, 1), pos(2, 0)],
+            step.get_enemy_positions(TileKind::Hill)
+        );
+        assert_eq!(
+            vec![pos(1, 2)],
+            step.get_enemy_positions(TileKind::Ant)
+        );
+    }
+
+    #[test]
+    fn get_ants_of_player() {
+        let step = BasicWorldStep::new_from_line_map(
+            "ab-
+             -B-
+             b-a",
+        );
+
+        assert_eq!(
+            vec![pos(0, 1), pos(1, 1), pos(2, 0)],
+            step.get_positions(Tile::Ant(1))
+        );
+    }
+
     #[test]
     fn no_hills_at_all() {
         let step_without_hills =
@@ -0,0 +1,160 @@
+use crate::world_step::*;
+use ants_ai_challenge_api::*;
+use std::collections::HashMap;
+
+/// Extra costs of the CostLayer used by the agent, configurable
+/// with the `step_costs` line of the strategy config.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepCosts {
+    /// Added within attack radius of enemy ants.
+    pub danger_cost: usize,
+    /// Added on positions of my ants.
+    pub crowd_cost: usize,
+}
+
+impl Default for StepCosts {
+    fn default() -> StepCosts {
+        StepCosts {
+            danger_cost: 4,
+            crowd_cost: 1,
+        }
+    }
+}
+
+/// Makes some positions more costly to move onto, letting searches
+/// find paths around them: positions within attack radius of enemy
+/// ants, and positions crowded by my own ants. Other positions keep
+/// the cost of the delegate.
+pub struct CostLayer<S: WorldStep> {
+    delegate: S,
+    extra_costs: HashMap<Position, usize>,
+}
+
+impl<S: WorldStep> CostLayer<S> {
+    /// Create layer adding 'danger_cost' within attack radius of each
+    /// enemy ant, and 'crowd_cost' on each of my ants.
+    pub fn new(
+        delegate: S,
+        attack_radius2: u32,
+        danger_cost: usize,
+        crowd_cost: usize,
+    ) -> CostLayer<S> {
+        let mut extra_costs = HashMap::new();
+
+        let enemies = delegate.get_enemy_positions(TileKind::Ant);
+        for p in positions_within(
+            &enemies,
+            attack_radius2,
+            delegate.size(),
+        ) {
+            *extra_costs.entry(p).or_insert(0) += danger_cost;
+        }
+        for p in delegate.all_my_ants() {
+            *extra_costs.entry(p).or_insert(0) += crowd_cost;
+        }
+
+        CostLayer {
+            delegate,
+            extra_costs,
+        }
+    }
+}
+
+impl CostLayer<BasicWorldStep> {
+    #[cfg(test)]
+    pub fn new_from_line_map(
+        map: &'static str,
+        attack_radius2: u32,
+        danger_cost: usize,
+        crowd_cost: usize,
+    ) -> CostLayer<BasicWorldStep> {
+        let inner = BasicWorldStep::new_from_line_map(map);
+        CostLayer::new(inner, attack_radius2, danger_cost, crowd_cost)
+    }
+}
+
+impl<S: WorldStep> WorldStep for CostLayer<S> {
+    fn add_order(&mut self, order: Order) -> &mut dyn WorldStep {
+        self.delegate.add_order(order);
+        self
+    }
+
+    fn get_orders(&self) -> Orders {
+        self.delegate.get_orders()
+    }
+
+    fn size(&self) -> &Position {
+        self.delegate.size()
+    }
+
+    fn all_my_ants(&self) -> Vec<Position> {
+        self.delegate.all_my_ants()
+    }
+
+    fn available_directions(&self, p: &Position) -> Vec<Direction> {
+        self.delegate.available_directions(p)
+    }
+
+    fn step_cost(&self, pos: &Position) -> usize {
+        self.delegate.step_cost(pos)
+            + self.extra_costs.get(pos).cloned().unwrap_or(0)
+    }
+
+    fn distance_fields(&self) -> Option<&DistanceFields> {
+        self.delegate.distance_fields()
+    }
+
+    fn last_seen(&self, pos: &Position) -> Option<u32> {
+        self.delegate.last_seen(pos)
+    }
+
+    fn remembered_enemy_ants(&self) -> Vec<(Position, u8, u32)> {
+        self.delegate.remembered_enemy_ants()
+    }
+
+    fn tile(&self, pos: &Position) -> Tile {
+        self.delegate.tile(pos)
+    }
+
+    fn get_positions(&self, tile: Tile) -> Vec<Position> {
+        self.delegate.get_positions(tile)
+    }
+
+    fn is_visible(&self, pos: &Position) -> bool {
+        self.delegate.is_visible(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn costly_near_enemies_and_on_my_ants() {
+        let step = CostLayer::new_from_line_map(
+            "a------
+             -----b-
+             -------",
+            1,
+            10,
+            3,
+        );
+
+        let costs: Vec<usize> =
+            (0..7).map(|col| step.step_cost(&pos(1, col))).collect();
+
+        assert_eq!(costs, vec![1, 1, 1, 1, 11, 11, 11]);
+        assert_eq!(step.step_cost(&pos(0, 5)), 11);
+        assert_eq!(step.step_cost(&pos(0, 0)), 4);
+        assert_eq!(step.step_cost(&pos(2, 2)), 1);
+    }
+
+    #[test]
+    fn costs_pass_through_other_world_steps() {
+        let step = AvoidWaterFilter::new(
+            CostLayer::new_from_line_map("ab", 1, 10, 3),
+        );
+
+        assert_eq!(step.step_cost(&pos(0, 0)), 14);
+    }
+}
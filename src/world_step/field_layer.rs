@@ -0,0 +1,110 @@
+use crate::deadline::Deadline;
+use crate::world_step::*;
+use ants_ai_challenge_api::*;
+
+/// Floods the distance fields from food, enemy hills and unexplored
+/// positions once, when the world step of the turn is created,
+/// letting all strategies of the turn read their gradients.
+pub struct FieldLayer<S: WorldStep> {
+    delegate: S,
+    fields: DistanceFields,
+}
+
+impl<S: WorldStep> FieldLayer<S> {
+    /// Create layer with fields flooded up to the cutoff length, or
+    /// until the deadline is reached.
+    pub fn new(
+        delegate: S,
+        cutoff_len: usize,
+        deadline: &Deadline,
+    ) -> FieldLayer<S> {
+        let fields =
+            DistanceFields::flood(&delegate, cutoff_len, deadline);
+        FieldLayer { delegate, fields }
+    }
+}
+
+impl FieldLayer<AvoidWaterFilter<BasicWorldStep>> {
+    #[cfg(test)]
+    pub fn new_from_line_map(
+        map: &'static str,
+        cutoff_len: usize,
+    ) -> FieldLayer<AvoidWaterFilter<BasicWorldStep>> {
+        let inner = AvoidWaterFilter::new_from_line_map(map);
+        FieldLayer::new(inner, cutoff_len, &Deadline::never())
+    }
+}
+
+impl<S: WorldStep> WorldStep for FieldLayer<S> {
+    fn add_order(&mut self, order: Order) -> &mut dyn WorldStep {
+        self.delegate.add_order(order);
+        self
+    }
+
+    fn get_orders(&self) -> Orders {
+        self.delegate.get_orders()
+    }
+
+    fn size(&self) -> &Position {
+        self.delegate.size()
+    }
+
+    fn all_my_ants(&self) -> Vec<Position> {
+        self.delegate.all_my_ants()
+    }
+
+    fn available_directions(&self, p: &Position) -> Vec<Direction> {
+        self.delegate.available_directions(p)
+    }
+
+    fn step_cost(&self, pos: &Position) -> usize {
+        self.delegate.step_cost(pos)
+    }
+
+    fn distance_fields(&self) -> Option<&DistanceFields> {
+        Some(&self.fields)
+    }
+
+    fn last_seen(&self, pos: &Position) -> Option<u32> {
+        self.delegate.last_seen(pos)
+    }
+
+    fn remembered_enemy_ants(&self) -> Vec<(Position, u8, u32)> {
+        self.delegate.remembered_enemy_ants()
+    }
+
+    fn tile(&self, pos: &Position) -> Tile {
+        self.delegate.tile(pos)
+    }
+
+    fn get_positions(&self, tile: Tile) -> Vec<Position> {
+        self.delegate.get_positions(tile)
+    }
+
+    fn is_visible(&self, pos: &Position) -> bool {
+        self.delegate.is_visible(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_are_flooded_once_and_shared() {
+        let step = FieldLayer::new_from_line_map(
+            "-a-*-
+             -%%%-
+             --1--
+             -%%%-",
+            10,
+        );
+        let fields = step.distance_fields().expect("flooded");
+
+        assert_eq!(Some(2), fields.food.distance(&pos(0, 1)));
+        assert_eq!(Some(5), fields.enemy_hills.distance(&pos(0, 1)));
+        assert!(AvoidWaterFilter::new(step)
+            .distance_fields()
+            .is_some());
+    }
+}
@@ -0,0 +1,178 @@
+use crate::world_step::*;
+use ants_ai_challenge_api::*;
+
+/// Same world step as BasicWorldStep, but with all tiles put in a
+/// grid once when created, giving tile lookups in constant time
+/// instead of scanning all ants, hills, food and water. Worth it
+/// when the step is searched more than a few times, e.g. once per
+/// turn.
+pub struct GridWorldStep {
+    given_orders: Vec<Order>,
+    world: WorldState,
+    size: Position,
+    tiles: Vec<Tile>,
+}
+
+impl GridWorldStep {
+    pub fn new(world: WorldState, size: Position) -> GridWorldStep {
+        let cols = size.col as usize;
+        let mut tiles = vec![Tile::Empty; size.row as usize * cols];
+
+        let index = |p: &Position| {
+            if p.row < size.row && p.col < size.col {
+                Some(p.row as usize * cols + p.col as usize)
+            } else {
+                None
+            }
+        };
+        let mut put = |positions: &[Position], tile: Tile| {
+            for i in positions.iter().flat_map(index) {
+                tiles[i] = tile;
+            }
+        };
+
+        // Later puts take precedence, so players with lower index
+        // are put last.
+        put(&world.waters, Tile::Water);
+        put(&world.foods, Tile::Food);
+        for (player, hills) in world.hills.iter().enumerate().rev() {
+            put(hills, Tile::Hill(player as u8));
+        }
+
+        for (player, ants) in world.live_ants.iter().enumerate().rev()
+        {
+            let player = player as u8;
+            for i in ants.iter().flat_map(index) {
+                tiles[i] = match tiles[i] {
+                    Tile::Hill(hill) | Tile::AntOnHill(_, hill) => {
+                        Tile::AntOnHill(player, hill)
+                    }
+                    _ => Tile::Ant(player),
+                };
+            }
+        }
+
+        GridWorldStep {
+            given_orders: vec![],
+            world,
+            size,
+            tiles,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn new_from_line_map(map: &'static str) -> GridWorldStep {
+        GridWorldStep::new(world(map), size_of_world(map))
+    }
+}
+
+impl WorldStep for GridWorldStep {
+    fn add_order(&mut self, order: Order) -> &mut dyn WorldStep {
+        self.given_orders.push(order);
+        self
+    }
+
+    fn get_orders(&self) -> Orders {
+        self.given_orders.clone()
+    }
+
+    fn size(&self) -> &Position {
+        &self.size
+    }
+
+    fn all_my_ants(&self) -> Vec<Position> {
+        self.world.live_ants_for_player(0)
+    }
+
+    fn available_directions(
+        &self,
+        _pos: &Position,
+    ) -> Vec<Direction> {
+        vec![North, South, East, West]
+    }
+
+    fn tile(&self, pos: &Position) -> Tile {
+        if pos.row < self.size.row && pos.col < self.size.col {
+            self.tiles[pos.row as usize * self.size.col as usize
+                + pos.col as usize]
+        } else {
+            Tile::Empty
+        }
+    }
+
+    fn get_positions(&self, tile: Tile) -> Vec<Position> {
+        match tile {
+            Tile::Food => self.world.foods.clone(),
+            Tile::Water => self.world.waters.clone(),
+            Tile::Hill(p) => match self.world.hills.get(p as usize) {
+                Some(hills) => hills.clone(),
+                None => vec![],
+            },
+            Tile::Ant(p) => {
+                match self.world.live_ants.get(p as usize) {
+                    Some(ants) => ants.clone(),
+                    None => vec![],
+                }
+            }
+            _ => vec![],
+        }
+    }
+
+    fn is_visible(&self, _pos: &Position) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_tiles_as_basic_world_step() {
+        let map = "%%*!-..a
+                   abcdefgh
+                   ABCDEFGH
+                   01234567";
+
+        assert_eq!(
+            GridWorldStep::new_from_line_map(map).format("", false),
+            BasicWorldStep::new_from_line_map(map).format("", false)
+        );
+    }
+
+    #[test]
+    fn same_positions_as_basic_world_step() {
+        let map = "02-b
+                   -1Ac
+                   2*%a";
+        let grid = GridWorldStep::new_from_line_map(map);
+        let basic = BasicWorldStep::new_from_line_map(map);
+
+        for tile in &[
+            Tile::Food,
+            Tile::Water,
+            Tile::Hill(0),
+            Tile::Hill(2),
+            Tile::Ant(0),
+            Tile::Ant(1),
+        ] {
+            assert_eq!(
+                grid.get_positions(*tile),
+                basic.get_positions(*tile)
+            );
+        }
+        assert_eq!(
+            grid.get_enemy_positions(TileKind::Ant),
+            basic.get_enemy_positions(TileKind::Ant)
+        );
+    }
+
+    #[test]
+    fn positions_outside_world_are_empty() {
+        let step = GridWorldStep::new_from_line_map("%%%\n%%%");
+
+        assert!(step.tile(&pos(0, 3)) == Tile::Empty);
+        assert!(step.tile(&pos(2, 0)) == Tile::Empty);
+        assert!(step.tile(&pos(1, 2)) == Tile::Water);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::world_memory::WorldMemory;
+use crate::world_step::*;
+use ants_ai_challenge_api::*;
+use std::collections::HashMap;
+
+/// Exposes the turn remembered food, hills and enemy ants were last
+/// seen, and the enemy ants seen in the last turns, see WorldMemory.
+/// Enemy ants out of sight for too long are already forgotten by the
+/// memory.
+pub struct MemoryLayer<S: WorldStep> {
+    delegate: S,
+    last_seen: HashMap<Position, u32>,
+    enemy_ants: Vec<(Position, u8, u32)>,
+}
+
+impl<S: WorldStep> MemoryLayer<S> {
+    pub fn new(delegate: S, memory: &WorldMemory) -> MemoryLayer<S> {
+        MemoryLayer {
+            delegate,
+            last_seen: memory.last_seen_positions(),
+            enemy_ants: memory.enemy_ants(),
+        }
+    }
+}
+
+impl<S: WorldStep> WorldStep for MemoryLayer<S> {
+    fn add_order(&mut self, order: Order) -> &mut dyn WorldStep {
+        self.delegate.add_order(order);
+        self
+    }
+
+    fn get_orders(&self) -> Orders {
+        self.delegate.get_orders()
+    }
+
+    fn size(&self) -> &Position {
+        self.delegate.size()
+    }
+
+    fn all_my_ants(&self) -> Vec<Position> {
+        self.delegate.all_my_ants()
+    }
+
+    fn available_directions(&self, p: &Position) -> Vec<Direction> {
+        self.delegate.available_directions(p)
+    }
+
+    fn step_cost(&self, pos: &Position) -> usize {
+        self.delegate.step_cost(pos)
+    }
+
+    fn distance_fields(&self) -> Option<&DistanceFields> {
+        self.delegate.distance_fields()
+    }
+
+    fn last_seen(&self, pos: &Position) -> Option<u32> {
+        self.last_seen.get(pos).cloned()
+    }
+
+    fn remembered_enemy_ants(&self) -> Vec<(Position, u8, u32)> {
+        self.enemy_ants.clone()
+    }
+
+    fn tile(&self, pos: &Position) -> Tile {
+        self.delegate.tile(pos)
+    }
+
+    fn get_positions(&self, tile: Tile) -> Vec<Position> {
+        self.delegate.get_positions(tile)
+    }
+
+    fn is_visible(&self, pos: &Position) -> bool {
+        self.delegate.is_visible(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// World step of the turn, as planned in by the agent.
+    fn step(
+        memory: &mut WorldMemory,
+        map: &'static str,
+        turn: u32,
+    ) -> AvoidWaterFilter<MemoryLayer<BasicWorldStep>> {
+        let world = world(map);
+        let size = size_of_world(map);
+        let visible = positions_within(
+            &world.live_ants_for_player(0),
+            2,
+            &size,
+        );
+        memory.update(&world, turn, &visible);
+        AvoidWaterFilter::new(MemoryLayer::new(
+            BasicWorldStep::new(
+                memory.remembered_world(&world),
+                size,
+            ),
+            memory,
+        ))
+    }
+
+    #[test]
+    fn remembered_positions_with_turn_last_seen() {
+        let mut memory = WorldMemory::default();
+
+        step(
+            &mut memory,
+            "-*1------
+             -ab------
+             ---------",
+            3,
+        );
+        let step = step(
+            &mut memory,
+            "---------
+             ---------
+             --------a",
+            5,
+        );
+
+        assert_eq!(step.get_positions(Tile::Food), vec![pos(0, 1)]);
+        assert_eq!(
+            step.get_positions(Tile::Hill(1)),
+            vec![pos(0, 2)]
+        );
+        assert_eq!(step.last_seen(&pos(0, 1)), Some(3));
+        assert_eq!(step.last_seen(&pos(0, 2)), Some(3));
+        assert_eq!(step.last_seen(&pos(2, 2)), None);
+        assert_eq!(
+            step.remembered_enemy_ants(),
+            vec![(pos(1, 2), 1, 3)]
+        );
+    }
+
+    #[test]
+    fn forget_enemy_ants_out_of_sight_for_long() {
+        let mut memory = WorldMemory::default();
+
+        step(
+            &mut memory,
+            "-b-------
+             -a-------
+             ---------",
+            1,
+        );
+        let step = step(
+            &mut memory,
+            "---------
+             ---------
+             -------a-",
+            100,
+        );
+
+        assert_eq!(step.remembered_enemy_ants(), vec![]);
+        assert_eq!(step.last_seen(&pos(0, 1)), None);
+    }
+}
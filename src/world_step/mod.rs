@@ -1,17 +1,29 @@
 pub mod ant_crash_filter;
 pub mod avoid_water_filter;
 pub mod basic_world_step;
+pub mod cost_layer;
+pub mod field_layer;
+pub mod grid_world_step;
+pub mod memory_layer;
+pub mod visibility_layer;
 
 pub use ant_crash_filter::*;
 pub use avoid_water_filter::*;
 pub use basic_world_step::*;
+pub use cost_layer::*;
+pub use field_layer::*;
+pub use grid_world_step::*;
+pub use memory_layer::*;
+pub use visibility_layer::*;
 
+use crate::strategy::search::DistanceFields;
 #[cfg(test)]
 use crate::utilities::*;
 use ants_ai_challenge_api::*;
 use std::collections::HashMap;
+use std::collections::HashSet;
 
-#[derive(PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Tile {
     Ant(u8),
     AntOnHill(u8, u8),
@@ -19,14 +31,85 @@ pub enum Tile {
     Water,
     Food,
     Hill(u8),
+    Unknown,
+}
+
+/// Kind of tile owned by a player, regardless of owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileKind {
+    Ant,
+    Hill,
+}
+
+/// Number of players supported, including me as player zero.
+pub const MAX_PLAYERS: u8 = 10;
+
+/// Squared euclidean distance between two positions, taking the
+/// wrap-around at the world borders into account.
+pub fn distance2(a: &Position, b: &Position, size: &Position) -> u32 {
+    let row_diff = (i64::from(a.row) - i64::from(b.row)).abs();
+    let col_diff = (i64::from(a.col) - i64::from(b.col)).abs();
+
+    let row_distance =
+        std::cmp::min(row_diff, i64::from(size.row) - row_diff);
+    let col_distance =
+        std::cmp::min(col_diff, i64::from(size.col) - col_diff);
+
+    (row_distance * row_distance + col_distance * col_distance) as u32
+}
+
+/// All positions within the given squared radius from any of the
+/// given centers, e.g. the positions visible to a set of ants.
+pub fn positions_within(
+    centers: &[Position],
+    radius2: u32,
+    size: &Position,
+) -> HashSet<Position> {
+    let radius = f64::from(radius2).sqrt() as i64;
+    let rows = i64::from(size.row);
+    let cols = i64::from(size.col);
+
+    let mut offsets: Vec<(i64, i64)> = vec![];
+    for row in -radius..=radius {
+        for col in -radius..=radius {
+            if (row * row + col * col) as u32 <= radius2 {
+                offsets.push((row, col));
+            }
+        }
+    }
+
+    let mut result = HashSet::new();
+    if rows == 0 || cols == 0 {
+        return result;
+    }
+
+    for center in centers {
+        for (row, col) in &offsets {
+            let r = (i64::from(center.row) + row).rem_euclid(rows);
+            let c = (i64::from(center.col) + col).rem_euclid(cols);
+            result.insert(pos(r as u16, c as u16));
+        }
+    }
+    result
+}
+
+/// Direction leading back where given direction came from.
+pub fn opposite(dir: Direction) -> Direction {
+    match dir {
+        North => South,
+        South => North,
+        East => West,
+        West => East,
+    }
 }
 
 fn add(c: char, incement: u8) -> char {
     (c as u8 + incement) as char
 }
 
-/// Game world simulation step state.
-pub trait WorldStep {
+/// Game world simulation step state. Shareable between threads for
+/// reading, e.g. by parallel searches.
+pub trait WorldStep: Sync {
     // Add an ant movement order.
     fn add_order(&mut self, order: Order) -> &mut dyn WorldStep;
 
@@ -40,10 +123,67 @@ pub trait WorldStep {
 
     fn available_directions(&self, pos: &Position) -> Vec<Direction>;
 
+    // Directions to the neighbours able to move to given position,
+    // i.e. the available directions in reverse, e.g. for searching
+    // backwards from a target.
+    fn arriving_directions(&self, pos: &Position) -> Vec<Direction> {
+        [North, East, South, West]
+            .iter()
+            .cloned()
+            .filter(|dir| {
+                let neighbour =
+                    pos.order(*dir).target_pos(self.size());
+                self.available_directions(&neighbour)
+                    .contains(&opposite(*dir))
+            })
+            .collect()
+    }
+
+    // Cost of moving onto given position, e.g. higher inside enemy
+    // attack radius. At least 1, so that the Manhattan distance is
+    // never more than the cost of a path.
+    fn step_cost(&self, _pos: &Position) -> usize {
+        1
+    }
+
+    // Distance fields shared by the strategies of the turn, if
+    // flooded, see FieldLayer.
+    fn distance_fields(&self) -> Option<&DistanceFields> {
+        None
+    }
+
+    // Turn when the food, hill or enemy ant at given position was
+    // last seen, if remembered, see MemoryLayer.
+    fn last_seen(&self, _pos: &Position) -> Option<u32> {
+        None
+    }
+
+    // Enemy ants seen in the last turns, with owner and the turn
+    // each was last seen, sorted by position, see MemoryLayer.
+    fn remembered_enemy_ants(&self) -> Vec<(Position, u8, u32)> {
+        vec![]
+    }
+
     fn tile(&self, pos: &Position) -> Tile;
 
     fn get_positions(&self, tile: Tile) -> Vec<Position>;
 
+    // Positions of given kind owned by any enemy player, in player
+    // order.
+    fn get_enemy_positions(&self, kind: TileKind) -> Vec<Position> {
+        (1..MAX_PLAYERS)
+            .flat_map(|player| {
+                self.get_positions(match kind {
+                    TileKind::Ant => Tile::Ant(player),
+                    TileKind::Hill => Tile::Hill(player),
+                })
+            })
+            .collect()
+    }
+
+    // Is position currently visible to my ants.
+    fn is_visible(&self, pos: &Position) -> bool;
+
     fn format(&self, indent: &str, annotate: bool) -> String {
         let orders = self.get_orders();
         let mut order_map = HashMap::<Position, Direction>::new();
@@ -63,6 +203,7 @@ pub trait WorldStep {
                     Tile::Hill(p) => add('0', p),
                     Tile::Food => '*',
                     Tile::Water => '%',
+                    Tile::Unknown => '?',
                 };
                 result += &identifier.to_string();
 
@@ -86,6 +227,48 @@ pub trait WorldStep {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[test]
+    fn distance2_wraps_around() {
+        let size = &pos(10, 10);
+        assert_eq!(5, distance2(&pos(1, 1), &pos(2, 3), size));
+        assert_eq!(5, distance2(&pos(0, 0), &pos(9, 8), size));
+        assert_eq!(0, distance2(&pos(4, 4), &pos(4, 4), size));
+    }
+
+    #[test]
+    fn positions_within_radius() {
+        let size = &pos(5, 6);
+        let map = "x-----
+                   xx---x
+                   x-----
+                   ------
+                   ------";
+
+        assert_eq!(
+            positions_of_x(map),
+            positions_within(&[pos(1, 0)], 1, size)
+        );
+    }
+
+    #[test]
+    fn arriving_directions_are_reversed_available_directions() {
+        let step = AvoidWaterFilter::new_from_line_map(
+            "---
+             %-%
+             -%-",
+        );
+
+        assert_eq!(vec![North], step.arriving_directions(&pos(1, 1)));
+        assert_eq!(
+            vec![East, South, West],
+            step.arriving_directions(&pos(0, 1))
+        );
+        assert_eq!(
+            Vec::<Direction>::new(),
+            step.arriving_directions(&pos(2, 1))
+        );
+    }
+
     #[test]
     fn can_print_step() {
         let step = BasicWorldStep::new_from_line_map(
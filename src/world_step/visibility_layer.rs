@@ -0,0 +1,166 @@
+use crate::world_step::*;
+use ants_ai_challenge_api::*;
+use std::collections::HashSet;
+
+/// Keeps track of which positions are currently visible from my ants
+/// and which have never been seen. Never seen positions are reported
+/// as `Tile::Unknown`.
+pub struct VisibilityLayer<S: WorldStep> {
+    delegate: S,
+    visible: HashSet<Position>,
+    seen: HashSet<Position>,
+}
+
+impl<S: WorldStep> VisibilityLayer<S> {
+    /// Create layer with visibility computed from the view radius of
+    /// my ants, where 'seen_before' are positions seen in earlier
+    /// turns.
+    pub fn new(
+        delegate: S,
+        view_radius2: u32,
+        seen_before: &HashSet<Position>,
+    ) -> VisibilityLayer<S> {
+        let visible = positions_within(
+            &delegate.all_my_ants(),
+            view_radius2,
+            delegate.size(),
+        );
+        let seen = seen_before.union(&visible).cloned().collect();
+
+        VisibilityLayer {
+            delegate,
+            visible,
+            seen,
+        }
+    }
+}
+
+impl VisibilityLayer<BasicWorldStep> {
+    #[cfg(test)]
+    pub fn new_from_line_map(
+        map: &'static str,
+        view_radius2: u32,
+    ) -> VisibilityLayer<BasicWorldStep> {
+        let inner = BasicWorldStep::new_from_line_map(map);
+        VisibilityLayer::new(
+            inner,
+            view_radius2,
+            &seen_positions(map),
+        )
+    }
+}
+
+impl<S: WorldStep> WorldStep for VisibilityLayer<S> {
+    fn add_order(&mut self, order: Order) -> &mut dyn WorldStep {
+        self.delegate.add_order(order);
+        self
+    }
+
+    fn get_orders(&self) -> Orders {
+        self.delegate.get_orders()
+    }
+
+    fn size(&self) -> &Position {
+        self.delegate.size()
+    }
+
+    fn all_my_ants(&self) -> Vec<Position> {
+        self.delegate.all_my_ants()
+    }
+
+    fn available_directions(&self, p: &Position) -> Vec<Direction> {
+        self.delegate.available_directions(p)
+    }
+
+    fn tile(&self, pos: &Position) -> Tile {
+        if self.seen.contains(pos) {
+            self.delegate.tile(pos)
+        } else {
+            Tile::Unknown
+        }
+    }
+
+    fn step_cost(&self, pos: &Position) -> usize {
+        self.delegate.step_cost(pos)
+    }
+
+    fn distance_fields(&self) -> Option<&DistanceFields> {
+        self.delegate.distance_fields()
+    }
+
+    fn last_seen(&self, pos: &Position) -> Option<u32> {
+        self.delegate.last_seen(pos)
+    }
+
+    fn remembered_enemy_ants(&self) -> Vec<(Position, u8, u32)> {
+        self.delegate.remembered_enemy_ants()
+    }
+
+    fn get_positions(&self, tile: Tile) -> Vec<Position> {
+        self.delegate.get_positions(tile)
+    }
+
+    fn is_visible(&self, pos: &Position) -> bool {
+        self.visible.contains(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visible_within_view_radius() {
+        let map = "------
+                   -a----
+                   ------
+                   ------";
+        let step = VisibilityLayer::new_from_line_map(map, 1);
+
+        let actual: HashSet<Position> = seen_positions(map)
+            .into_iter()
+            .filter(|p| step.is_visible(p))
+            .collect();
+
+        assert_eq!(
+            positions_of_x(
+                "-x----
+                 xxx---
+                 -x----
+                 ------"
+            ),
+            actual
+        );
+    }
+
+    #[test]
+    fn never_seen_tiles_are_unknown() {
+        let step = VisibilityLayer::new_from_line_map(
+            "??????
+             ?a-*??
+             ??????",
+            1,
+        );
+
+        assert!(step.tile(&pos(0, 0)) == Tile::Unknown);
+        assert!(step.tile(&pos(1, 3)) == Tile::Food);
+
+        // Seen, but not currently visible
+        assert!(!step.is_visible(&pos(1, 3)));
+
+        // Never seen before, but currently visible
+        assert!(step.tile(&pos(0, 1)) == Tile::Empty);
+    }
+
+    #[test]
+    fn can_print_unknown_tiles() {
+        let step = VisibilityLayer::new_from_line_map(
+            "????
+             ?a-*
+             ??%?",
+            1,
+        );
+
+        assert_eq!("|?.??\n|.a.*\n|?.%?\n", step.format("|", false));
+    }
+}